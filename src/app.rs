@@ -1,10 +1,16 @@
+use std::cmp::min;
+
 use anyhow::Result;
 
 use crossterm::event::KeyEvent;
 use ratatui::prelude::{Constraint, Direction, Layout, Rect};
 
 use crate::{
-    components::{Component, ContextHelp, KeySelector, ValueEditor},
+    components::{
+        AlarmIndicator, AlarmList, ClusterDashboard, Component, ContextHelp, HistoryBrowser,
+        KeySelector, LeaseBrowser, LockInspector, PermissionInspector, RoleList, StagingReview,
+        TxnBuilder, UserList, ValueEditor, WatchPanel,
+    },
     events::{Event, KeyEventState},
     ui::Frame,
     SharedState,
@@ -13,6 +19,18 @@ use crate::{
 pub struct App {
     key_selector: KeySelector,
     value_editor: ValueEditor,
+    history_browser: HistoryBrowser,
+    watch_panel: WatchPanel,
+    lease_browser: LeaseBrowser,
+    txn_builder: TxnBuilder,
+    staging_review: StagingReview,
+    cluster_dashboard: ClusterDashboard,
+    alarm_list: AlarmList,
+    user_list: UserList,
+    role_list: RoleList,
+    permission_inspector: PermissionInspector,
+    lock_inspector: LockInspector,
+    alarm_indicator: AlarmIndicator,
     context_help: ContextHelp,
 
     shared_state: SharedState,
@@ -24,13 +42,29 @@ impl App {
         let mut this = Self {
             key_selector: KeySelector::new(shared_state.clone()),
             value_editor: ValueEditor::new(shared_state.clone()),
+            history_browser: HistoryBrowser::new(shared_state.clone()),
+            watch_panel: WatchPanel::new(shared_state.clone()),
+            lease_browser: LeaseBrowser::new(shared_state.clone()),
+            txn_builder: TxnBuilder::new(shared_state.clone()),
+            staging_review: StagingReview::new(shared_state.clone()),
+            cluster_dashboard: ClusterDashboard::new(shared_state.clone()),
+            alarm_list: AlarmList::new(shared_state.clone()),
+            user_list: UserList::new(shared_state.clone()),
+            role_list: RoleList::new(shared_state.clone()),
+            permission_inspector: PermissionInspector::new(shared_state.clone()),
+            lock_inspector: LockInspector::new(shared_state.clone()),
+            alarm_indicator: AlarmIndicator::new(shared_state.clone()),
             context_help: ContextHelp::new(),
 
             shared_state,
             app_result: None,
         };
 
-        this.key_selector.show();
+        if let Some(path) = this.shared_state.cli().replay.clone() {
+            this.watch_panel.open_replay(path);
+        } else {
+            this.key_selector.show();
+        }
 
         this
     }
@@ -45,7 +79,9 @@ impl App {
 
     pub fn handle_event(&mut self, event: Event) -> Result<()> {
         match event {
-            Event::Keyboard(kb) => drop(self.handle_key_event(kb)?),
+            Event::Keyboard(kb) => {
+                self.handle_key_event(kb)?;
+            }
             Event::Quit(x) => {
                 self.app_result = Some(x);
             }
@@ -53,9 +89,121 @@ impl App {
                 self.key_selector.show();
                 self.value_editor.hide();
             }
-            Event::KeySelected { key, value } => {
+            Event::KeySelected {
+                key,
+                value,
+                is_read_only,
+            } => {
                 self.key_selector.hide();
                 self.value_editor.open_key(key, value);
+                if is_read_only {
+                    self.value_editor.set_read_only();
+                }
+            }
+            Event::NewKey { key, lease } => {
+                self.key_selector.hide();
+                self.value_editor.open_new_key(key, lease);
+            }
+            Event::ShowKeyHistory(key) => {
+                self.key_selector.hide();
+                self.history_browser.open_key(key);
+            }
+            Event::KeyHistoryDone => {
+                self.key_selector.show();
+                self.history_browser.hide();
+            }
+            Event::ShowWatch(prefix) => {
+                self.key_selector.hide();
+                self.watch_panel.open_prefix(prefix);
+            }
+            Event::WatchDone => {
+                // there is no connection to etcd in replay mode
+                if self.shared_state.cli().replay.is_some() {
+                    self.app_result = Some(Ok(()));
+                }
+                self.key_selector.show();
+                self.watch_panel.hide();
+            }
+            Event::ShowLeases => {
+                self.key_selector.hide();
+                self.lease_browser.show();
+            }
+            Event::LeasesDone => {
+                self.key_selector.show();
+                self.lease_browser.hide();
+            }
+            Event::ShowStagingReview => {
+                self.key_selector.hide();
+                self.staging_review.show();
+            }
+            Event::StagingReviewDone => {
+                self.key_selector.show();
+                self.staging_review.hide();
+            }
+            Event::ShowTxnBuilder => {
+                self.key_selector.hide();
+                self.txn_builder.show();
+            }
+            Event::TxnBuilderDone => {
+                self.key_selector.show();
+                self.txn_builder.hide();
+            }
+            Event::ShowCluster(key) => {
+                self.key_selector.hide();
+                self.cluster_dashboard.open(key);
+            }
+            Event::ClusterDone => {
+                self.key_selector.show();
+                self.cluster_dashboard.hide();
+            }
+            Event::ShowAlarms => {
+                self.key_selector.hide();
+                self.alarm_list.show();
+            }
+            Event::AlarmsDone => {
+                self.key_selector.show();
+                self.alarm_list.hide();
+            }
+            Event::ShowUsers => {
+                self.key_selector.hide();
+                self.user_list.show();
+            }
+            Event::UsersDone => {
+                self.key_selector.show();
+                self.user_list.hide();
+            }
+            Event::ShowRoles(key) => {
+                self.key_selector.hide();
+                self.role_list.open(key);
+            }
+            Event::RolesDone => {
+                self.key_selector.show();
+                self.role_list.hide();
+            }
+            Event::ShowPermissions(key) => {
+                self.key_selector.hide();
+                self.permission_inspector.open(key);
+            }
+            Event::PermissionsDone => {
+                self.key_selector.show();
+                self.permission_inspector.hide();
+            }
+            Event::ShowLocks(key) => {
+                self.key_selector.hide();
+                self.lock_inspector.open(key);
+            }
+            Event::LocksDone => {
+                self.key_selector.show();
+                self.lock_inspector.hide();
+            }
+            Event::RevealKey(key) => {
+                self.lease_browser.hide();
+                self.key_selector.reveal_key(key);
+            }
+            // handled by main loop
+            Event::EditExternally(_) => {}
+            Event::ExternalEditDone(result) => {
+                self.value_editor.external_edit_done(result);
             }
             Event::Tui(_) | Event::Tick => {
                 self.update()?;
@@ -73,27 +221,72 @@ impl Component for App {
     fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
         key_event!(self.key_selector.handle_key_event(event));
         key_event!(self.value_editor.handle_key_event(event));
+        key_event!(self.history_browser.handle_key_event(event));
+        key_event!(self.watch_panel.handle_key_event(event));
+        key_event!(self.lease_browser.handle_key_event(event));
+        key_event!(self.txn_builder.handle_key_event(event));
+        key_event!(self.staging_review.handle_key_event(event));
+        key_event!(self.cluster_dashboard.handle_key_event(event));
+        key_event!(self.alarm_list.handle_key_event(event));
+        key_event!(self.user_list.handle_key_event(event));
+        key_event!(self.role_list.handle_key_event(event));
+        key_event!(self.permission_inspector.handle_key_event(event));
+        key_event!(self.lock_inspector.handle_key_event(event));
         Ok(KeyEventState::Consumed)
     }
 
     fn update(&mut self) -> Result<()> {
         self.key_selector.update()?;
         self.value_editor.update()?;
+        self.history_browser.update()?;
+        self.watch_panel.update()?;
+        self.lease_browser.update()?;
+        self.txn_builder.update()?;
+        self.staging_review.update()?;
+        self.cluster_dashboard.update()?;
+        self.alarm_list.update()?;
+        self.user_list.update()?;
+        self.role_list.update()?;
+        self.permission_inspector.update()?;
+        self.lock_inspector.update()?;
+        self.alarm_indicator.update()?;
         Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        self.context_help.set_help(self.context_help());
+        // hints must not crowd out main widget on small terminal
+        let help_height = min(
+            self.context_help.height(frame.size().width),
+            frame.size().height / 3,
+        );
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(vec![Constraint::Min(0), Constraint::Max(2)])
+            .constraints(vec![
+                Constraint::Min(0),
+                Constraint::Length(self.alarm_indicator.height()),
+                Constraint::Length(help_height),
+            ])
             .split(frame.size());
 
-        self.context_help.set_help(self.context_help());
-        self.context_help.draw(frame, layout[1]);
+        self.alarm_indicator.draw(frame, layout[1]);
+        self.context_help.draw(frame, layout[2]);
 
         let main_widget_layout_rect = layout[0];
         self.key_selector.draw(frame, main_widget_layout_rect);
         self.value_editor.draw(frame, main_widget_layout_rect);
+        self.history_browser.draw(frame, main_widget_layout_rect);
+        self.watch_panel.draw(frame, main_widget_layout_rect);
+        self.lease_browser.draw(frame, main_widget_layout_rect);
+        self.txn_builder.draw(frame, main_widget_layout_rect);
+        self.staging_review.draw(frame, main_widget_layout_rect);
+        self.cluster_dashboard.draw(frame, main_widget_layout_rect);
+        self.alarm_list.draw(frame, main_widget_layout_rect);
+        self.user_list.draw(frame, main_widget_layout_rect);
+        self.role_list.draw(frame, main_widget_layout_rect);
+        self.permission_inspector
+            .draw(frame, main_widget_layout_rect);
+        self.lock_inspector.draw(frame, main_widget_layout_rect);
     }
 
     fn set_visibility(&mut self, _: bool) {}
@@ -107,6 +300,17 @@ impl Component for App {
 
         helps.extend(self.key_selector.context_help());
         helps.extend(self.value_editor.context_help());
+        helps.extend(self.history_browser.context_help());
+        helps.extend(self.watch_panel.context_help());
+        helps.extend(self.lease_browser.context_help());
+        helps.extend(self.txn_builder.context_help());
+        helps.extend(self.staging_review.context_help());
+        helps.extend(self.cluster_dashboard.context_help());
+        helps.extend(self.alarm_list.context_help());
+        helps.extend(self.user_list.context_help());
+        helps.extend(self.role_list.context_help());
+        helps.extend(self.permission_inspector.context_help());
+        helps.extend(self.lock_inspector.context_help());
 
         helps
     }
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
 #[derive(Debug, Parser)]
@@ -19,6 +21,17 @@ pub struct Cli {
     /// User password
     #[arg(short, long, env = "ETCD_PASSWORD")]
     pub password: Option<String>,
+
+    /// Number of latest revisions, which compaction keeps by default
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    pub compact_retain: i64,
+
+    /// Record watched events to file (JSON Lines)
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+    /// Replay events, recorded with `--record`, without connecting to etcd
+    #[arg(long, value_name = "FILE", conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
 }
 
 impl Cli {
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use ratatui::{prelude::Rect, style::Stylize, widgets::Paragraph};
+
+use crate::{components::Component, shared_state::Alarm, ui::Frame, utils::AsyncTask, SharedState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Status line, shown while cluster has active alarms.
+pub struct AlarmIndicator {
+    shared_state: SharedState,
+
+    polled_at: Option<Instant>,
+    poll_task: AsyncTask<Result<Vec<Alarm>>>,
+}
+
+impl AlarmIndicator {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            polled_at: None,
+            poll_task: AsyncTask::new(shared_state),
+        }
+    }
+
+    /// Height of status line, 0 if there are no active alarms.
+    pub fn height(&self) -> u16 {
+        if self.is_visible() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl Component for AlarmIndicator {
+    fn update(&mut self) -> Result<()> {
+        // there is no cluster to poll, when replaying recorded events
+        if self.shared_state.cli().replay.is_some() {
+            return Ok(());
+        }
+
+        // alarms are cached by shared state, so result itself isn't needed
+        if self.poll_task.try_ready().is_some() {
+            self.polled_at = Some(Instant::now());
+        }
+
+        if !self.poll_task.is_active()
+            && self.polled_at.is_none_or(|x| x.elapsed() >= POLL_INTERVAL)
+        {
+            self.poll_task
+                .start(|s| async move { s.load_alarms().await });
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let alarms = self
+                .shared_state
+                .active_alarms()
+                .iter()
+                .map(Alarm::to_string)
+                .collect::<Vec<_>>();
+            let text = format!(
+                " ALARM: {}, press (A) in key selection for details",
+                alarms.join(", ")
+            );
+            frame.render_widget(Paragraph::new(text).white().on_red().bold(), rect);
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        !self.shared_state.active_alarms().is_empty()
+    }
+}
@@ -0,0 +1,260 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph, Wrap},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::{Alarm, AlarmKind},
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, MessagePopup};
+
+/// List of active alarms of cluster.
+pub struct AlarmList {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    alarms: Vec<Alarm>,
+    list_state: ListState,
+
+    load_alarms_task: ForegroundTask<Result<Vec<Alarm>>>,
+    disarm_task: ForegroundTask<Result<()>>,
+    disarm_confirmation_popup: Option<ConfirmationPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl AlarmList {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            alarms: vec![],
+            list_state: ListState::default(),
+
+            load_alarms_task: ForegroundTask::new("Loading alarms", shared_state.clone()),
+            disarm_task: ForegroundTask::new("Disarming alarm", shared_state),
+            disarm_confirmation_popup: None,
+            message_popup: None,
+        }
+    }
+
+    fn selected_alarm(&self) -> Option<&Alarm> {
+        self.list_state.selected().and_then(|x| self.alarms.get(x))
+    }
+
+    fn reload_alarms(&mut self) {
+        self.load_alarms_task
+            .start(|s| async move { s.load_alarms().await });
+    }
+
+    fn prompt_disarm(&mut self) {
+        if let Some(alarm) = self.selected_alarm() {
+            let mut popup =
+                ConfirmationPopup::new(format!("Disarm {alarm}?"), self.shared_state.clone());
+            popup.show();
+            self.disarm_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn disarm_selected_alarm(&mut self) {
+        if let Some(alarm) = self.selected_alarm().cloned() {
+            self.disarm_task
+                .start(|s| async move { s.disarm_alarm(&alarm).await });
+        }
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+}
+
+impl Component for AlarmList {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_alarms_task.handle_key_event(event));
+            key_event!(self.disarm_task.handle_key_event(event));
+            if let Some(ref mut x) = self.disarm_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(x.saturating_add(1), self.alarms.len().saturating_sub(1))
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Char('d') | Key::Delete,
+                    ..
+                } => {
+                    self.prompt_disarm();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_alarms();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::AlarmsDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_alarms_task.try_ready() {
+            match result {
+                Ok(alarms) => {
+                    self.alarms = alarms;
+                    let selected = self
+                        .list_state
+                        .selected()
+                        .map_or(0, |x| min(x, self.alarms.len().saturating_sub(1)));
+                    self.list_state
+                        .select((!self.alarms.is_empty()).then_some(selected));
+                }
+                Err(err) => self.show_message("Cannot load alarms", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.disarm_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot disarm alarm", &err.to_string());
+            }
+            self.reload_alarms();
+        }
+
+        if let Some(ref mut x) = self.disarm_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.disarm_selected_alarm();
+                }
+                self.disarm_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(40), Constraint::Min(0)])
+                .split(rect);
+
+            let items = self
+                .alarms
+                .iter()
+                .map(|x| ListItem::new(Line::styled(x.to_string(), Style::default().red())))
+                .collect::<Vec<_>>();
+            let list_widget = List::new(items)
+                .block(main_titled_block(format!("Alarms ({})", self.alarms.len())))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let description = match self.selected_alarm().map(|x| x.kind) {
+                Some(AlarmKind::NoSpace) => {
+                    "Database of member exceeded its space quota. Until alarm is disarmed, \
+                     cluster accepts only reads and deletes. Free space by compacting and \
+                     defragmenting (cluster view) before disarming, otherwise alarm is raised \
+                     again."
+                }
+                Some(AlarmKind::Corrupt) => {
+                    "Data of member is inconsistent with rest of cluster, which rejects writes \
+                     until alarm is disarmed. Restore member from healthy one before disarming."
+                }
+                None => "No active alarms",
+            };
+            let description_widget = Paragraph::new(description)
+                .wrap(Wrap { trim: true })
+                .block(main_titled_block("Description"));
+            frame.render_widget(description_widget, layout[1]);
+
+            self.load_alarms_task.draw(frame, rect);
+            self.disarm_task.draw(frame, rect);
+            if let Some(ref mut x) = self.disarm_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_alarms_task.is_visible() {
+                return self.load_alarms_task.context_help();
+            }
+
+            if self.disarm_task.is_visible() {
+                return self.disarm_task.context_help();
+            }
+
+            if let Some(ref x) = self.disarm_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(d/Del) disarm alarm".into(),
+                "(R) reload".into(),
+                "(Esc) return to key selection".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.reload_alarms();
+    }
+}
@@ -0,0 +1,1121 @@
+use std::{
+    cmp::min,
+    collections::VecDeque,
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Cell, Paragraph, Row, Table, TableState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::{ConsistencyReport, MemberConsistency, MemberStatus},
+    ui::{main_titled_block, Frame},
+    utils::{format_bytes, AsyncTask},
+    SharedState,
+};
+
+use super::{
+    Component, ConfirmationPopup, ForegroundTask, InputPopup, MessagePopup, SelectionPopup,
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Change of cluster, confirmed before execution.
+#[derive(Clone, Debug)]
+enum ClusterOperation {
+    Compact {
+        revision: i64,
+        physical: bool,
+    },
+    Add {
+        peer_urls: Vec<String>,
+        is_learner: bool,
+    },
+    Remove {
+        id: u64,
+        name: String,
+    },
+    UpdatePeerUrls {
+        id: u64,
+        name: String,
+        peer_urls: Vec<String>,
+    },
+    Promote {
+        id: u64,
+        name: String,
+    },
+    MoveLeader {
+        id: u64,
+        name: String,
+    },
+}
+
+impl ClusterOperation {
+    /// Name of member, which has to be typed to confirm destructive operation.
+    fn typed_confirmation(&self) -> Option<&str> {
+        match self {
+            Self::Remove { name, .. } | Self::UpdatePeerUrls { name, .. } => Some(name),
+            Self::Compact { .. }
+            | Self::Add { .. }
+            | Self::Promote { .. }
+            | Self::MoveLeader { .. } => None,
+        }
+    }
+
+    /// Fill operation with input of user, i.e. revision or comma separated peer URLs.
+    fn apply_input(&mut self, input: &str) -> Result<()> {
+        match self {
+            Self::Compact { revision, .. } => {
+                *revision = input
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid revision '{input}'"))?;
+            }
+            Self::Add { peer_urls, .. } | Self::UpdatePeerUrls { peer_urls, .. } => {
+                *peer_urls = input
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|x| !x.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            Self::Remove { .. } | Self::Promote { .. } | Self::MoveLeader { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Execute operation and return message describing its result.
+    async fn execute(self, shared_state: SharedState) -> Result<String> {
+        match self {
+            Self::Compact { revision, physical } => {
+                shared_state.compact(revision, physical).await?;
+                Ok(format!("Compacted keyspace up to revision {revision}"))
+            }
+            Self::Add {
+                peer_urls,
+                is_learner,
+            } => {
+                let id = shared_state.add_member(&peer_urls, is_learner).await?;
+                Ok(format!(
+                    "Added member {id:x}. Start it with --initial-cluster-state=existing."
+                ))
+            }
+            Self::Remove { id, name } => {
+                shared_state.remove_member(id).await?;
+                Ok(format!("Removed member '{name}'"))
+            }
+            Self::UpdatePeerUrls {
+                id,
+                name,
+                peer_urls,
+            } => {
+                shared_state.update_member_peer_urls(id, &peer_urls).await?;
+                Ok(format!(
+                    "Updated peer URLs of member '{name}' to {}",
+                    peer_urls.join(", ")
+                ))
+            }
+            Self::Promote { id, name } => {
+                shared_state.promote_member(id).await?;
+                Ok(format!("Promoted member '{name}' to voting member"))
+            }
+            Self::MoveLeader { id, name } => {
+                shared_state.move_leader(id).await?;
+                Ok(format!("Member '{name}' is leader now"))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ClusterOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compact {
+                revision,
+                physical: false,
+            } => write!(f, "Compact keyspace up to revision {revision}?"),
+            Self::Compact {
+                revision,
+                physical: true,
+            } => write!(
+                f,
+                "Compact keyspace up to revision {revision} and wait until it is physically applied?"
+            ),
+            Self::Add {
+                peer_urls,
+                is_learner: false,
+            } => write!(f, "Add member with peer URLs {}?", peer_urls.join(", ")),
+            Self::Add {
+                peer_urls,
+                is_learner: true,
+            } => write!(f, "Add learner with peer URLs {}?", peer_urls.join(", ")),
+            Self::Remove { name, .. } => write!(f, "Remove member '{name}' from cluster?"),
+            Self::UpdatePeerUrls {
+                name, peer_urls, ..
+            } => write!(
+                f,
+                "Change peer URLs of member '{name}' to {}?",
+                peer_urls.join(", ")
+            ),
+            Self::Promote { name, .. } => write!(f, "Promote learner '{name}'?"),
+            Self::MoveLeader { name, .. } => write!(f, "Transfer leadership to '{name}'?"),
+        }
+    }
+}
+
+/// Defragmentation of members one at a time.
+struct Defragmentation {
+    remaining: VecDeque<MemberStatus>,
+    total: usize,
+    /// Member being defragmented.
+    current: String,
+    /// Result for each member already defragmented.
+    reports: Vec<String>,
+}
+
+/// Members of cluster along with status of their endpoints.
+pub struct ClusterDashboard {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    members: Vec<MemberStatus>,
+    table_state: TableState,
+    /// Key selected in key selection, which can be compared across members.
+    selected_key: Option<String>,
+    /// Result of last consistency check.
+    consistency: Option<ConsistencyReport>,
+    /// Error of last refresh, if it failed.
+    error: Option<String>,
+    refreshed_at: Option<Instant>,
+
+    refresh_task: AsyncTask<Result<Vec<MemberStatus>>>,
+
+    /// Operation waiting for input or for confirmation.
+    pending_operation: Option<ClusterOperation>,
+    input_popup: Option<InputPopup>,
+    confirmation_popup: Option<ConfirmationPopup>,
+    operation_task: ForegroundTask<Result<String>>,
+
+    /// Members waiting for confirmation of their defragmentation.
+    pending_defragmentation: Vec<MemberStatus>,
+    defragmentation_confirmation_popup: Option<ConfirmationPopup>,
+    defragmentation: Option<Defragmentation>,
+    defragmentation_task: ForegroundTask<Result<(i64, i64)>>,
+
+    /// Voting members, which leadership can be transferred to.
+    leader_candidates: Vec<MemberStatus>,
+    leader_selection_popup: Option<SelectionPopup>,
+
+    /// Member, whose snapshot is waiting for path of file.
+    snapshot_member: Option<MemberStatus>,
+    snapshot_path_popup: Option<InputPopup>,
+    /// Number of bytes of snapshot received so far.
+    snapshot_progress: Arc<AtomicU64>,
+    snapshot_task: ForegroundTask<Result<String>>,
+
+    consistency_task: ForegroundTask<Result<ConsistencyReport>>,
+
+    message_popup: Option<MessagePopup>,
+}
+
+impl ClusterDashboard {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            members: vec![],
+            table_state: TableState::default(),
+            selected_key: None,
+            consistency: None,
+            error: None,
+            refreshed_at: None,
+
+            refresh_task: AsyncTask::new(shared_state.clone()),
+
+            pending_operation: None,
+            input_popup: None,
+            confirmation_popup: None,
+            operation_task: ForegroundTask::new("Changing cluster", shared_state.clone()),
+
+            pending_defragmentation: vec![],
+            defragmentation_confirmation_popup: None,
+            defragmentation: None,
+            defragmentation_task: ForegroundTask::new("Defragmenting", shared_state.clone()),
+
+            leader_candidates: vec![],
+            leader_selection_popup: None,
+
+            snapshot_member: None,
+            snapshot_path_popup: None,
+            snapshot_progress: Arc::new(AtomicU64::new(0)),
+            snapshot_task: ForegroundTask::new("Saving snapshot", shared_state.clone()),
+
+            consistency_task: ForegroundTask::new("Checking consistency", shared_state),
+
+            message_popup: None,
+        }
+    }
+
+    fn prompt_compact(&mut self, physical: bool) {
+        let latest_revision = self
+            .members
+            .iter()
+            .filter_map(|x| x.status.as_ref().ok().map(|x| x.revision))
+            .max();
+        let mut popup = InputPopup::new(
+            "Compact up to revision",
+            "revision",
+            self.shared_state.clone(),
+        );
+        if let Some(x) = latest_revision {
+            let revision = (x - self.shared_state.cli().compact_retain).max(1);
+            popup = popup.with_value(&revision.to_string());
+        }
+        popup.show();
+        self.input_popup = Some(popup);
+        self.pending_operation = Some(ClusterOperation::Compact {
+            revision: 0,
+            physical,
+        });
+    }
+
+    fn prompt_defragment(&mut self, all_members: bool) {
+        let members = if all_members {
+            self.members.clone()
+        } else {
+            self.selected_member().cloned().into_iter().collect()
+        };
+        if members.is_empty() {
+            return;
+        }
+        let description = match members.as_slice() {
+            [x] => format!("Defragment member '{}'?", member_label(x)),
+            x => format!("Defragment all {} members one at a time?", x.len()),
+        };
+        let mut popup = ConfirmationPopup::new(description, self.shared_state.clone());
+        popup.show();
+        self.defragmentation_confirmation_popup = Some(popup);
+        self.pending_defragmentation = members;
+    }
+
+    fn start_defragmentation(&mut self, members: Vec<MemberStatus>) {
+        self.defragmentation = Some(Defragmentation {
+            total: members.len(),
+            remaining: members.into(),
+            current: String::new(),
+            reports: vec![],
+        });
+        self.defragment_next();
+    }
+
+    /// Start defragmentation of next member or report results, if there are none left.
+    fn defragment_next(&mut self) {
+        let Some(ref mut defragmentation) = self.defragmentation else {
+            return;
+        };
+        match defragmentation.remaining.pop_front() {
+            Some(member) => {
+                defragmentation.current = member_label(&member);
+                let done = defragmentation.total - defragmentation.remaining.len();
+                self.defragmentation_task.set_description(format!(
+                    "Defragmenting '{}' ({done} of {})",
+                    defragmentation.current, defragmentation.total
+                ));
+                let client_urls = member.client_urls;
+                self.defragmentation_task
+                    .start(|s| async move { s.defragment_member(&client_urls).await });
+            }
+            None => {
+                let message = defragmentation.reports.join("\n");
+                self.defragmentation = None;
+                self.show_message("Defragmentation", &message);
+                self.refresh();
+            }
+        }
+    }
+
+    fn prompt_move_leader(&mut self) {
+        let leader = self.leader();
+        self.leader_candidates = self
+            .members
+            .iter()
+            .filter(|x| !x.is_learner && Some(x.id) != leader)
+            .cloned()
+            .collect();
+        if self.leader_candidates.is_empty() {
+            self.show_message(
+                "Cannot move leader",
+                "There is no other voting member to transfer leadership to",
+            );
+            return;
+        }
+        let items = self
+            .leader_candidates
+            .iter()
+            .map(|x| format!("{} ({:x})", member_label(x), x.id))
+            .collect();
+        let mut popup =
+            SelectionPopup::new("Transfer leadership to", items, self.shared_state.clone());
+        popup.show();
+        self.leader_selection_popup = Some(popup);
+    }
+
+    fn prompt_snapshot_path(&mut self) {
+        let Some(member) = self.selected_member().cloned() else {
+            return;
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_secs());
+        let mut popup = InputPopup::new(
+            format!("Save snapshot of '{}' to", member_label(&member)),
+            "path",
+            self.shared_state.clone(),
+        )
+        .with_value(&format!(
+            "snapshot-{}-{timestamp}.db",
+            member_label(&member)
+        ));
+        popup.show();
+        self.snapshot_path_popup = Some(popup);
+        self.snapshot_member = Some(member);
+    }
+
+    fn save_snapshot(&mut self, member: MemberStatus, path: PathBuf) {
+        self.snapshot_progress = Arc::new(AtomicU64::new(0));
+        let progress = self.snapshot_progress.clone();
+        self.snapshot_task.start(|s| async move {
+            let size = s
+                .save_snapshot(&member.client_urls, &path, progress)
+                .await?;
+            Ok(format!(
+                "Saved snapshot of '{}' to '{}' ({}), size and checksum verified",
+                member_label(&member),
+                path.display(),
+                format_bytes(size as i64)
+            ))
+        });
+    }
+
+    fn prompt_add(&mut self, is_learner: bool) {
+        let title = if is_learner {
+            "Peer URLs of new learner"
+        } else {
+            "Peer URLs of new member"
+        };
+        let mut popup = InputPopup::new(title, "http://host:2380, ...", self.shared_state.clone());
+        popup.show();
+        self.input_popup = Some(popup);
+        self.pending_operation = Some(ClusterOperation::Add {
+            peer_urls: vec![],
+            is_learner,
+        });
+    }
+
+    fn prompt_update_peer_urls(&mut self) {
+        let Some(member) = self.selected_member() else {
+            return;
+        };
+        let mut popup = InputPopup::new(
+            format!("Peer URLs of '{}'", member_label(member)),
+            "http://host:2380, ...",
+            self.shared_state.clone(),
+        )
+        .with_value(&member.peer_urls.join(", "));
+        popup.show();
+        self.pending_operation = Some(ClusterOperation::UpdatePeerUrls {
+            id: member.id,
+            name: member_label(member),
+            peer_urls: vec![],
+        });
+        self.input_popup = Some(popup);
+    }
+
+    fn prompt_remove(&mut self) {
+        if let Some(member) = self.selected_member() {
+            self.confirm(ClusterOperation::Remove {
+                id: member.id,
+                name: member_label(member),
+            });
+        }
+    }
+
+    fn prompt_promote(&mut self) {
+        match self.selected_member() {
+            Some(member) if member.is_learner => {
+                self.confirm(ClusterOperation::Promote {
+                    id: member.id,
+                    name: member_label(member),
+                });
+            }
+            Some(member) => {
+                let message = format!("Member '{}' is not a learner", member_label(member));
+                self.show_message("Cannot promote", &message);
+            }
+            None => {}
+        }
+    }
+
+    fn confirm(&mut self, operation: ClusterOperation) {
+        let mut popup = ConfirmationPopup::new(&operation, self.shared_state.clone());
+        if let Some(name) = operation.typed_confirmation() {
+            popup = popup.with_typed_confirmation(name);
+        }
+        popup.show();
+        self.confirmation_popup = Some(popup);
+        self.pending_operation = Some(operation);
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    pub fn open(&mut self, selected_key: Option<String>) {
+        self.selected_key = selected_key;
+        self.show();
+    }
+
+    fn check_consistency(&mut self, with_key: bool) {
+        let key = if with_key {
+            match self.selected_key.clone() {
+                Some(x) => Some(x),
+                None => {
+                    self.show_message("Cannot check key", "No key was selected in key selection");
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        self.consistency_task
+            .start(|s| async move { s.check_consistency(key.as_deref()).await });
+    }
+
+    fn refresh(&mut self) {
+        if !self.refresh_task.is_active() {
+            self.refresh_task
+                .start(|s| async move { s.load_cluster_status().await });
+        }
+    }
+
+    fn selected_member(&self) -> Option<&MemberStatus> {
+        self.table_state
+            .selected()
+            .and_then(|x| self.members.get(x))
+    }
+
+    /// ID of leader, as seen by any member.
+    fn leader(&self) -> Option<u64> {
+        self.members
+            .iter()
+            .find_map(|x| x.status.as_ref().ok().map(|x| x.leader))
+    }
+
+    fn member_rows(&self) -> Vec<Row<'static>> {
+        let leader = self.leader();
+        self.members
+            .iter()
+            .map(|x| {
+                let role = if Some(x.id) == leader {
+                    "leader"
+                } else if x.is_learner {
+                    "learner"
+                } else {
+                    "follower"
+                };
+                let mut cells = vec![
+                    Cell::from(member_label(x)),
+                    Cell::from(format!("{:x}", x.id)),
+                    Cell::from(role),
+                ];
+                match x.status {
+                    Ok(ref status) => {
+                        cells.extend([
+                            Cell::from(status.version.clone()),
+                            Cell::from(format_bytes(status.db_size)),
+                            Cell::from(format_bytes(status.db_size_in_use)),
+                            Cell::from(status.raft_term.to_string()),
+                            Cell::from(status.raft_index.to_string()),
+                        ]);
+                        if status.errors.is_empty() {
+                            Row::new(cells)
+                        } else {
+                            Row::new(cells).style(Style::default().yellow())
+                        }
+                    }
+                    Err(_) => {
+                        cells.push(Cell::from("unavailable"));
+                        Row::new(cells).style(Style::default().red())
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn consistency_lines(&self) -> Vec<Line<'static>> {
+        let Some(ref report) = self.consistency else {
+            return vec![Line::from("Not checked yet")];
+        };
+
+        let label = |x: &MemberConsistency| {
+            if x.name.is_empty() {
+                format!("{:x}", x.id)
+            } else {
+                x.name.clone()
+            }
+        };
+        let summary = |is_mismatch| {
+            if is_mismatch {
+                Line::styled("MISMATCH", Style::default().red().bold())
+            } else {
+                Line::styled("consistent", Style::default().green())
+            }
+        };
+        let mut lines = vec![
+            Line::from(format!("Keyspace hash at revision {}:", report.revision)),
+            summary(report.has_hash_mismatch()),
+        ];
+        for member in &report.members {
+            let text = match member.hash {
+                Ok(x) => format!(
+                    "  {}: {:08x} (compacted at {})",
+                    label(member),
+                    x.hash,
+                    x.compact_revision
+                ),
+                Err(ref err) => format!("  {}: {err}", label(member)),
+            };
+            lines.push(Line::from(text));
+        }
+
+        if let Some(ref key) = report.key {
+            lines.push(Line::default());
+            lines.push(Line::from(format!("Key '{key}' read from each member:")));
+            lines.push(summary(report.has_key_mismatch()));
+            for member in &report.members {
+                let text = match member.key_value {
+                    Some(Ok(Some(ref x))) => format!(
+                        "  {}: mod revision {}, value {:?}",
+                        label(member),
+                        x.mod_revision,
+                        x.value.chars().take(40).collect::<String>()
+                    ),
+                    Some(Ok(None)) => format!("  {}: missing", label(member)),
+                    Some(Err(ref err)) => format!("  {}: {err}", label(member)),
+                    None => continue,
+                };
+                lines.push(Line::from(text));
+            }
+        }
+        lines
+    }
+
+    fn member_details(&self) -> Vec<Line<'static>> {
+        let Some(member) = self.selected_member() else {
+            return vec![];
+        };
+
+        let mut lines = vec![
+            Line::from(format!("Peer URLs: {}", member.peer_urls.join(", "))),
+            Line::from(format!("Client URLs: {}", member.client_urls.join(", "))),
+            Line::from(format!("Learner: {}", member.is_learner)),
+        ];
+        match member.status {
+            Ok(ref status) => {
+                lines.push(Line::from(format!("Revision: {}", status.revision)));
+                lines.push(Line::from(format!(
+                    "Raft applied index: {}",
+                    status.raft_applied_index
+                )));
+                if status.errors.is_empty() {
+                    lines.push(Line::from("Errors: none"));
+                } else {
+                    lines.push(Line::from("Errors:"));
+                    lines.extend(
+                        status
+                            .errors
+                            .iter()
+                            .map(|x| Line::styled(format!("  {x}"), Style::default().yellow())),
+                    );
+                }
+            }
+            Err(ref err) => {
+                lines.push(Line::styled(
+                    format!("Status unavailable: {err}"),
+                    Style::default().red(),
+                ));
+            }
+        }
+        lines
+    }
+}
+
+impl Component for ClusterDashboard {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            if let Some(ref mut x) = self.input_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.operation_task.handle_key_event(event));
+            if let Some(ref mut x) = self.defragmentation_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.defragmentation_task.handle_key_event(event));
+            if let Some(ref mut x) = self.leader_selection_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.snapshot_path_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.snapshot_task.handle_key_event(event));
+            key_event!(self.consistency_task.handle_key_event(event));
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.table_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.table_state.select(Some(selected_id.map_or(0, |x| {
+                        min(x.saturating_add(1), self.members.len().saturating_sub(1))
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.table_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.refresh();
+                }
+                Input {
+                    key: Key::Char('c'),
+                    ..
+                } => {
+                    self.prompt_compact(false);
+                }
+                Input {
+                    key: Key::Char('C'),
+                    ..
+                } => {
+                    self.prompt_compact(true);
+                }
+                Input {
+                    key: Key::Char('f'),
+                    ..
+                } => {
+                    self.prompt_defragment(false);
+                }
+                Input {
+                    key: Key::Char('F'),
+                    ..
+                } => {
+                    self.prompt_defragment(true);
+                }
+                Input {
+                    key: Key::Char('k'),
+                    ..
+                } => {
+                    self.check_consistency(false);
+                }
+                Input {
+                    key: Key::Char('K'),
+                    ..
+                } => {
+                    self.check_consistency(true);
+                }
+                Input {
+                    key: Key::Char('m'),
+                    ..
+                } => {
+                    self.prompt_move_leader();
+                }
+                Input {
+                    key: Key::Char('s'),
+                    ..
+                } => {
+                    self.prompt_snapshot_path();
+                }
+                Input {
+                    key: Key::Char('a'),
+                    ..
+                } => {
+                    self.prompt_add(false);
+                }
+                Input {
+                    key: Key::Char('A'),
+                    ..
+                } => {
+                    self.prompt_add(true);
+                }
+                Input {
+                    key: Key::Char('u'),
+                    ..
+                } => {
+                    self.prompt_update_peer_urls();
+                }
+                Input {
+                    key: Key::Char('p'),
+                    ..
+                } => {
+                    self.prompt_promote();
+                }
+                Input {
+                    key: Key::Char('x') | Key::Delete,
+                    ..
+                } => {
+                    self.prompt_remove();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::ClusterDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+
+        if let Some(ref mut x) = self.input_popup {
+            if let Some(result) = x.status() {
+                let operation = self.pending_operation.take();
+                if let (Some(input), Some(mut operation)) = (result.into_done(), operation) {
+                    match operation.apply_input(&input) {
+                        Ok(()) => self.confirm(operation),
+                        Err(err) => self.show_message("Invalid input", &err.to_string()),
+                    }
+                }
+                self.input_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.confirmation_popup {
+            if let Some(result) = x.status() {
+                let operation = self.pending_operation.take();
+                if let (true, Some(operation)) = (result.is_yes(), operation) {
+                    self.operation_task
+                        .start(|s| async move { operation.execute(s).await });
+                }
+                self.confirmation_popup = None;
+            }
+        }
+
+        if let Some(result) = self.operation_task.try_ready() {
+            match result {
+                Ok(message) => self.show_message("Done", &message),
+                Err(err) => self.show_message("Operation failed", &err.to_string()),
+            }
+            self.refresh();
+        }
+
+        if let Some(ref mut x) = self.leader_selection_popup {
+            if let Some(result) = x.status() {
+                let candidates = std::mem::take(&mut self.leader_candidates);
+                if let Some(member) = result.into_done().and_then(|x| candidates.get(x)) {
+                    self.confirm(ClusterOperation::MoveLeader {
+                        id: member.id,
+                        name: member_label(member),
+                    });
+                }
+                self.leader_selection_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.snapshot_path_popup {
+            if let Some(result) = x.status() {
+                let member = self.snapshot_member.take();
+                if let (Some(path), Some(member)) = (result.into_done(), member) {
+                    self.save_snapshot(member, PathBuf::from(path));
+                }
+                self.snapshot_path_popup = None;
+            }
+        }
+
+        if self.snapshot_task.is_active() {
+            let received = self.snapshot_progress.load(Ordering::Relaxed);
+            self.snapshot_task.set_description(format!(
+                "Saving snapshot, {} received",
+                format_bytes(received as i64)
+            ));
+        }
+
+        if let Some(result) = self.consistency_task.try_ready() {
+            match result {
+                Ok(x) => self.consistency = Some(x),
+                Err(err) => self.show_message("Cannot check consistency", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.snapshot_task.try_ready() {
+            match result {
+                Ok(message) => self.show_message("Snapshot saved", &message),
+                Err(err) => self.show_message("Cannot save snapshot", &err.to_string()),
+            }
+        }
+
+        if let Some(ref mut x) = self.defragmentation_confirmation_popup {
+            if let Some(result) = x.status() {
+                let members = std::mem::take(&mut self.pending_defragmentation);
+                if result.is_yes() {
+                    self.start_defragmentation(members);
+                }
+                self.defragmentation_confirmation_popup = None;
+            }
+        }
+
+        if let Some(result) = self.defragmentation_task.try_ready() {
+            if let Some(ref mut x) = self.defragmentation {
+                match result {
+                    Ok((before, after)) => x.reports.push(format!(
+                        "{}: {} -> {}",
+                        x.current,
+                        format_bytes(before),
+                        format_bytes(after)
+                    )),
+                    Err(err) => {
+                        x.reports.push(format!("{}: failed: {err}", x.current));
+                        if !x.remaining.is_empty() {
+                            x.reports.push("Remaining members skipped".into());
+                            x.remaining.clear();
+                        }
+                    }
+                }
+            }
+            self.defragment_next();
+        } else if let Some(ref mut x) = self.defragmentation {
+            // task was cancelled
+            if !self.defragmentation_task.is_active() {
+                x.reports.push(format!(
+                    "{}: cancelled, defragmentation may still complete on member",
+                    x.current
+                ));
+                x.remaining.clear();
+                self.defragment_next();
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        if let Some(result) = self.refresh_task.try_ready() {
+            self.refreshed_at = Some(Instant::now());
+            match result {
+                Ok(members) => {
+                    let selected_id = self.selected_member().map(|x| x.id);
+                    self.members = members;
+                    self.error = None;
+                    let idx = selected_id
+                        .and_then(|id| self.members.iter().position(|x| x.id == id))
+                        .or((!self.members.is_empty()).then_some(0));
+                    self.table_state.select(idx);
+                }
+                Err(err) => self.error = Some(err.to_string()),
+            }
+        }
+
+        if self
+            .refreshed_at
+            .is_none_or(|x| x.elapsed() >= REFRESH_INTERVAL)
+        {
+            self.refresh();
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Percentage(60), Constraint::Min(0)])
+                .split(rect);
+
+            let title = match self.error {
+                Some(ref x) => Line::styled(
+                    format!("Cluster (refresh failed: {x})"),
+                    Style::default().red(),
+                ),
+                None => Line::from(format!("Cluster ({} members)", self.members.len())),
+            };
+            let header = Row::new(vec![
+                "Name", "ID", "Role", "Version", "DB size", "In use", "Term", "Index",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+            let widths = [
+                Constraint::Percentage(16),
+                Constraint::Length(17),
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(6),
+                Constraint::Min(8),
+            ];
+            let table = Table::new(self.member_rows())
+                .header(header)
+                .widths(&widths)
+                .block(main_titled_block(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(table, layout[0], &mut self.table_state);
+
+            let details_title = match self.selected_member() {
+                Some(x) => format!("Member '{}'", member_label(x)),
+                None => "Member".into(),
+            };
+            let bottom_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(50), Constraint::Min(0)])
+                .split(layout[1]);
+            let details =
+                Paragraph::new(self.member_details()).block(main_titled_block(details_title));
+            frame.render_widget(details, bottom_layout[0]);
+
+            let consistency =
+                Paragraph::new(self.consistency_lines()).block(main_titled_block("Consistency"));
+            frame.render_widget(consistency, bottom_layout[1]);
+
+            if let Some(ref mut x) = self.input_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.confirmation_popup {
+                x.draw(frame, rect);
+            }
+            self.operation_task.draw(frame, rect);
+            if let Some(ref mut x) = self.defragmentation_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            self.defragmentation_task.draw(frame, rect);
+            if let Some(ref mut x) = self.leader_selection_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.snapshot_path_popup {
+                x.draw(frame, rect);
+            }
+            self.snapshot_task.draw(frame, rect);
+            self.consistency_task.draw(frame, rect);
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if let Some(ref x) = self.input_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.confirmation_popup {
+                return x.context_help();
+            }
+
+            if self.operation_task.is_visible() {
+                return self.operation_task.context_help();
+            }
+
+            if let Some(ref x) = self.defragmentation_confirmation_popup {
+                return x.context_help();
+            }
+
+            if self.defragmentation_task.is_visible() {
+                return self.defragmentation_task.context_help();
+            }
+
+            if let Some(ref x) = self.leader_selection_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.snapshot_path_popup {
+                return x.context_help();
+            }
+
+            if self.snapshot_task.is_visible() {
+                return self.snapshot_task.context_help();
+            }
+
+            if self.consistency_task.is_visible() {
+                return self.consistency_task.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(a) add member".into(),
+                "(A) add learner".into(),
+                "(u) update peer URLs".into(),
+                "(p) promote learner".into(),
+                "(x/Del) remove member".into(),
+                "(c) compact".into(),
+                "(C) compact physically".into(),
+                "(f) defragment member".into(),
+                "(F) defragment all".into(),
+                "(s) save snapshot".into(),
+                "(m) move leader".into(),
+                "(k) check consistency".into(),
+                "(K) check consistency of selected key".into(),
+                "(R) refresh now".into(),
+                "(Esc) return to key selection".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.refresh();
+    }
+}
+
+/// Name of member, or its ID if it has not started yet and so has no name.
+fn member_label(member: &MemberStatus) -> String {
+    if member.name.is_empty() {
+        format!("{:x}", member.id)
+    } else {
+        member.name.clone()
+    }
+}
@@ -2,10 +2,10 @@ use anyhow::Result;
 use crossterm::event::KeyEvent;
 use ratatui::{
     prelude::{Alignment, Constraint, Direction, Layout, Rect},
-    style::Stylize,
-    widgets::{Borders, Clear, Paragraph},
+    style::{Style, Stylize},
+    widgets::{Borders, Clear, Paragraph, Wrap},
 };
-use tui_textarea::{Input, Key};
+use tui_textarea::{Input, Key, TextArea};
 
 use crate::{
     events::KeyEventState,
@@ -31,6 +31,11 @@ impl ConfirmationResult {
 pub struct ConfirmationPopup {
     description: String,
     result: Option<ConfirmationResult>,
+    /// Text, which has to be typed to confirm, instead of pressing `y`.
+    expected_input: Option<String>,
+    /// Consequences of confirming, shown below description.
+    explanation: Option<String>,
+    textarea: TextArea<'static>,
 
     is_visible: bool,
 
@@ -42,6 +47,9 @@ impl ConfirmationPopup {
         Self {
             description: description.to_string(),
             result: None,
+            expected_input: None,
+            explanation: None,
+            textarea: TextArea::default(),
 
             is_visible: false,
 
@@ -49,6 +57,31 @@ impl ConfirmationPopup {
         }
     }
 
+    /// Require typing `text` to confirm, used for destructive operations.
+    pub fn with_typed_confirmation(mut self, text: impl ToString) -> Self {
+        self.textarea.set_cursor_line_style(Style::default());
+        self.expected_input = Some(text.to_string());
+        self
+    }
+
+    /// Explain consequences of confirming, which popup is widened for.
+    pub fn with_explanation(mut self, text: impl ToString) -> Self {
+        self.explanation = Some(text.to_string());
+        self
+    }
+
+    /// Paragraph with explanation, preceded by empty line, along with its height.
+    fn explanation_paragraph(&self, width: u16) -> (Paragraph<'static>, u16) {
+        let Some(ref explanation) = self.explanation else {
+            return (Paragraph::new(""), 0);
+        };
+        let height = 1 + wrapped_height(explanation, width.saturating_sub(2) as usize);
+        let paragraph = Paragraph::new(format!("\n{explanation}"))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        (paragraph, height)
+    }
+
     fn set_result(&mut self, result: ConfirmationResult) -> Result<()> {
         self.result = Some(result);
         self.shared_state.tick()?;
@@ -63,6 +96,25 @@ impl ConfirmationPopup {
 impl Component for ConfirmationPopup {
     fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
         if self.is_visible() {
+            if let Some(ref expected) = self.expected_input {
+                match event.into() {
+                    Input { key: Key::Esc, .. } => {
+                        self.set_result(ConfirmationResult::Cancel)?;
+                    }
+                    Input {
+                        key: Key::Enter, ..
+                    } => {
+                        if self.textarea.lines().first() == Some(expected) {
+                            self.set_result(ConfirmationResult::Yes)?;
+                        }
+                    }
+                    input => {
+                        self.textarea.input(input);
+                    }
+                }
+                return Ok(KeyEventState::Consumed);
+            }
+
             match event.into() {
                 Input {
                     key: Key::Char('y'),
@@ -92,12 +144,50 @@ impl Component for ConfirmationPopup {
             .borders(Borders::ALL)
             .on_dark_gray();
 
+        let width = if self.explanation.is_some() { 60 } else { 50 };
+        let (explanation_paragraph, explanation_height) = self.explanation_paragraph(width);
+
+        if let Some(ref expected) = self.expected_input {
+            let rect = calculate_center_rect(width, 7 + explanation_height, frame.size());
+            let inner_layout = Layout::default()
+                .constraints(vec![
+                    Constraint::Max(2),
+                    Constraint::Length(explanation_height),
+                    Constraint::Max(1),
+                    Constraint::Max(1),
+                    Constraint::Min(0),
+                ])
+                .direction(Direction::Vertical)
+                .split(block.inner(rect));
+
+            let description_paragraph = Paragraph::new(self.description.clone())
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true });
+            let hint_paragraph =
+                Paragraph::new(format!("Type '{expected}' and press Enter to confirm"))
+                    .alignment(Alignment::Center);
+
+            frame.render_widget(Clear, rect);
+            frame.render_widget(block, rect);
+            frame.render_widget(description_paragraph, inner_layout[0]);
+            frame.render_widget(explanation_paragraph, inner_layout[1]);
+            frame.render_widget(hint_paragraph, inner_layout[2]);
+            frame.render_widget(self.textarea.widget(), inner_layout[3]);
+            return;
+        }
+
         // TODO: calculate size of widget from description
-        let rect = calculate_center_rect(30, 5, frame.size());
+        let width = if self.explanation.is_some() {
+            width
+        } else {
+            30
+        };
+        let rect = calculate_center_rect(width, 5 + explanation_height, frame.size());
 
         let inner_layout = Layout::default()
             .constraints(vec![
                 Constraint::Max(1),
+                Constraint::Length(explanation_height),
                 Constraint::Max(1),
                 Constraint::Max(1),
                 Constraint::Min(0),
@@ -112,7 +202,8 @@ impl Component for ConfirmationPopup {
         frame.render_widget(Clear, rect);
         frame.render_widget(block, rect);
         frame.render_widget(description_paragraph, inner_layout[0]);
-        frame.render_widget(y_n_esc_paragraph, inner_layout[2]);
+        frame.render_widget(explanation_paragraph, inner_layout[1]);
+        frame.render_widget(y_n_esc_paragraph, inner_layout[3]);
     }
 
     fn set_visibility(&mut self, value: bool) {
@@ -125,9 +216,34 @@ impl Component for ConfirmationPopup {
 
     fn context_help(&self) -> Vec<String> {
         if self.is_visible() {
+            if self.expected_input.is_some() {
+                return vec!["(Enter) confirm".into(), "(Esc) cancel".into()];
+            }
             vec!["(y) yes".into(), "(n) no".into(), "(Esc) cancel".into()]
         } else {
             vec![]
         }
     }
 }
+
+/// Number of lines, which `text` takes when wrapped at word boundaries to `width`.
+fn wrapped_height(text: &str, width: usize) -> u16 {
+    text.lines()
+        .map(|line| {
+            let mut height = 1;
+            let mut line_width = 0;
+            for word in line.split_whitespace() {
+                let word_width = word.chars().count();
+                if line_width == 0 {
+                    line_width = word_width;
+                } else if line_width + 1 + word_width > width {
+                    height += 1;
+                    line_width = word_width;
+                } else {
+                    line_width += 1 + word_width;
+                }
+            }
+            height
+        })
+        .sum()
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Stylize,
+    widgets::{Borders, Clear, Paragraph},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::{KeyValue, SharedState},
+    ui::{calculate_center_rect, titled_block, Frame},
+};
+
+use super::Component;
+
+#[derive(Copy, Clone, Debug)]
+pub enum ConflictResolution {
+    /// Save local value anyway.
+    Overwrite,
+    /// Discard local changes and load remote value.
+    Reload,
+    /// Three-way merge local and remote changes.
+    Merge,
+    Cancel,
+}
+
+/// Popup, shown when key was modified remotely while it was edited.
+pub struct ConflictPopup {
+    remote: Option<KeyValue>,
+    scroll: u16,
+    result: Option<ConflictResolution>,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl ConflictPopup {
+    pub fn new(remote: Option<KeyValue>, shared_state: SharedState) -> Self {
+        Self {
+            remote,
+            scroll: 0,
+            result: None,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    fn set_result(&mut self, result: ConflictResolution) -> Result<()> {
+        self.result = Some(result);
+        self.shared_state.tick()?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> Option<ConflictResolution> {
+        self.result
+    }
+
+    pub fn remote(&self) -> Option<&KeyValue> {
+        self.remote.as_ref()
+    }
+}
+
+impl Component for ConflictPopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            match event.into() {
+                Input {
+                    key: Key::Char('o'),
+                    ..
+                } => {
+                    self.set_result(ConflictResolution::Overwrite)?;
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } => {
+                    self.set_result(ConflictResolution::Reload)?;
+                }
+                Input {
+                    key: Key::Char('m'),
+                    ..
+                } => {
+                    self.set_result(ConflictResolution::Merge)?;
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.set_result(ConflictResolution::Cancel)?;
+                }
+                Input { key: Key::Down, .. } => {
+                    self.scroll = self.scroll.saturating_add(1);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.scroll = self.scroll.saturating_sub(1);
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        let title = match self.remote {
+            Some(ref x) => format!("Key was modified remotely (revision {})", x.mod_revision),
+            None => "Key was deleted remotely".into(),
+        };
+        let block = titled_block(title).borders(Borders::ALL).on_dark_gray();
+
+        let frame_rect = frame.size();
+        let rect = calculate_center_rect(
+            frame_rect.width.saturating_mul(4) / 5,
+            frame_rect.height.saturating_mul(4) / 5,
+            frame_rect,
+        );
+
+        let inner_layout = Layout::default()
+            .constraints(vec![Constraint::Min(0), Constraint::Max(1)])
+            .direction(Direction::Vertical)
+            .split(block.inner(rect));
+
+        let remote_value = self
+            .remote
+            .as_ref()
+            .map(|x| x.value.clone())
+            .unwrap_or_default();
+        let remote_paragraph = Paragraph::new(remote_value).scroll((self.scroll, 0));
+        let actions_paragraph =
+            Paragraph::new("(o) overwrite, (r) reload, (m) merge, (Esc) cancel")
+                .alignment(Alignment::Center);
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(block, rect);
+        frame.render_widget(remote_paragraph, inner_layout[0]);
+        frame.render_widget(actions_paragraph, inner_layout[1]);
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            vec![
+                "(Up/Down) scroll remote value".into(),
+                "(o) overwrite remote value".into(),
+                "(r) reload remote value".into(),
+                "(m) merge".into(),
+                "(Esc) cancel".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+}
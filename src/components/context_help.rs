@@ -1,6 +1,7 @@
 use ratatui::{
     prelude::Rect,
-    widgets::{Padding, Paragraph},
+    text::Line,
+    widgets::{Padding, Paragraph, Wrap},
 };
 
 use crate::{
@@ -20,12 +21,57 @@ impl ContextHelp {
     pub fn set_help(&mut self, help: Vec<String>) {
         self.help = help;
     }
+
+    /// Hints wrapped to fit `width` of help area, without splitting any of them, unless
+    /// it doesn't fit on its own line.
+    fn lines(&self, width: u16) -> Vec<String> {
+        // horizontal padding of block
+        let width = width.saturating_sub(2).max(1) as usize;
+        let mut lines = vec![];
+        let mut line = String::new();
+        for (idx, hint) in self.help.iter().enumerate() {
+            let hint = if idx + 1 < self.help.len() {
+                format!("{hint},")
+            } else {
+                hint.clone()
+            };
+            if line.is_empty() {
+                line = hint;
+            } else if line.chars().count() + 1 + hint.chars().count() <= width {
+                line.push(' ');
+                line.push_str(&hint);
+            } else {
+                lines.push(std::mem::replace(&mut line, hint));
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Height of help area with hints wrapped to fit `width`, including title.
+    pub fn height(&self, width: u16) -> u16 {
+        let text_width = width.saturating_sub(2).max(1) as usize;
+        let lines = self
+            .lines(width)
+            .iter()
+            .map(|x| x.chars().count().div_ceil(text_width).max(1))
+            .sum::<usize>();
+        (lines + 1).min(u16::MAX as usize) as u16
+    }
 }
 
 impl Component for ContextHelp {
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
         if self.is_visible() {
-            let help = Paragraph::new(self.help.clone().join(", "))
+            let lines = self
+                .lines(rect.width)
+                .into_iter()
+                .map(Line::from)
+                .collect::<Vec<_>>();
+            let help = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
                 .block(main_titled_block("Hints").padding(Padding::new(1, 1, 0, 0)));
             frame.render_widget(help, rect);
         }
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Borders, Clear, Paragraph},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::SharedState,
+    ui::{calculate_center_rect, titled_block, unified_diff_lines, Frame},
+};
+
+use super::{confirmation_popup::ConfirmationResult, Component};
+
+/// Confirmation popup, which shows diff between two values.
+///
+/// In view-only mode it can only be closed with Esc (which gives `ConfirmationResult::Cancel`).
+/// View-only mode can also be used to show plain text.
+pub struct DiffPopup {
+    title: String,
+    diff: Vec<Line<'static>>,
+    scroll: u16,
+    is_view_only: bool,
+    result: Option<ConfirmationResult>,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl DiffPopup {
+    pub fn new(title: impl ToString, old: &str, new: &str, shared_state: SharedState) -> Self {
+        Self {
+            title: title.to_string(),
+            diff: unified_diff_lines(old, new),
+            scroll: 0,
+            is_view_only: false,
+            result: None,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    pub fn view_only(
+        title: impl ToString,
+        old: &str,
+        new: &str,
+        shared_state: SharedState,
+    ) -> Self {
+        Self {
+            is_view_only: true,
+            ..Self::new(title, old, new, shared_state)
+        }
+    }
+
+    pub fn text_view(title: impl ToString, text: &str, shared_state: SharedState) -> Self {
+        Self {
+            diff: text
+                .split('\n')
+                .map(|x| Line::from(x.to_string()))
+                .collect(),
+            ..Self::view_only(title, "", "", shared_state)
+        }
+    }
+
+    fn set_result(&mut self, result: ConfirmationResult) -> Result<()> {
+        self.result = Some(result);
+        self.shared_state.tick()?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> Option<ConfirmationResult> {
+        self.result
+    }
+}
+
+impl Component for DiffPopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            match event.into() {
+                Input {
+                    key: Key::Char('y'),
+                    ..
+                } if !self.is_view_only => {
+                    self.set_result(ConfirmationResult::Yes)?;
+                }
+                Input {
+                    key: Key::Char('n'),
+                    ..
+                } if !self.is_view_only => {
+                    self.set_result(ConfirmationResult::No)?;
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.set_result(ConfirmationResult::Cancel)?;
+                }
+                Input { key: Key::Down, .. } => {
+                    let max_scroll = self.diff.len().saturating_sub(1) as u16;
+                    self.scroll = self.scroll.saturating_add(1).min(max_scroll);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.scroll = self.scroll.saturating_sub(1);
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        let block = titled_block(self.title.clone())
+            .borders(Borders::ALL)
+            .on_dark_gray();
+
+        let frame_rect = frame.size();
+        let rect = calculate_center_rect(
+            frame_rect.width.saturating_mul(4) / 5,
+            frame_rect.height.saturating_mul(4) / 5,
+            frame_rect,
+        );
+
+        let inner_layout = Layout::default()
+            .constraints(vec![Constraint::Min(0), Constraint::Max(1)])
+            .direction(Direction::Vertical)
+            .split(block.inner(rect));
+
+        let diff_paragraph = Paragraph::new(self.diff.clone()).scroll((self.scroll, 0));
+        let actions = if self.is_view_only { "Esc" } else { "y/n/Esc" };
+        let y_n_esc_paragraph = Paragraph::new(actions).alignment(Alignment::Center);
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(block, rect);
+        frame.render_widget(diff_paragraph, inner_layout[0]);
+        frame.render_widget(y_n_esc_paragraph, inner_layout[1]);
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() && self.is_view_only {
+            vec!["(Up/Down) scroll diff".into(), "(Esc) close".into()]
+        } else if self.is_visible() {
+            vec![
+                "(Up/Down) scroll diff".into(),
+                "(y) yes".into(),
+                "(n) no".into(),
+                "(Esc) cancel".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+}
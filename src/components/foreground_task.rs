@@ -5,7 +5,7 @@ use crossterm::event::KeyEvent;
 use ratatui::{
     prelude::{Alignment, Rect},
     style::Stylize,
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 use tui_textarea::{Input, Key};
 
@@ -35,6 +35,11 @@ where
             task: AsyncTask::new(shared_state),
         }
     }
+
+    /// Change description, e.g. to report progress of multi-step operation.
+    pub fn set_description(&mut self, description: impl ToString) {
+        self.description = description.to_string();
+    }
 }
 
 impl<T> Deref for ForegroundTask<T> {
@@ -70,6 +75,7 @@ where
         if self.is_visible() {
             let paragraph = Paragraph::new(self.description.clone())
                 .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
                 .block(Block::default().borders(Borders::ALL).on_dark_gray());
             // TODO: calculate size of widget from description
             let rect = calculate_center_rect(30, 5, frame.size());
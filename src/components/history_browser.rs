@@ -0,0 +1,386 @@
+use std::cmp::{max, min};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::{KeyHistory, KeyValue, PutLease, PutResult},
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, DiffPopup, ForegroundTask, MessagePopup};
+
+/// Browser of past revisions of single key.
+pub struct HistoryBrowser {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    key: String,
+    history: Option<KeyHistory>,
+    list_state: ListState,
+    /// Revision, marked for comparison with selected one.
+    marked: Option<usize>,
+
+    load_history_task: ForegroundTask<Result<KeyHistory>>,
+    restore_task: ForegroundTask<Result<PutResult>>,
+    restore_confirmation_popup: Option<ConfirmationPopup>,
+    diff_popup: Option<DiffPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl HistoryBrowser {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            key: String::new(),
+            history: None,
+            list_state: ListState::default(),
+            marked: None,
+
+            load_history_task: ForegroundTask::new("Loading history", shared_state.clone()),
+            restore_task: ForegroundTask::new("Restoring revision", shared_state),
+            restore_confirmation_popup: None,
+            diff_popup: None,
+            message_popup: None,
+        }
+    }
+
+    pub fn open_key(&mut self, key: String) {
+        self.key = key;
+        self.history = None;
+        self.show();
+        self.reload_history();
+    }
+
+    fn revisions(&self) -> &[KeyValue] {
+        self.history
+            .as_ref()
+            .map_or(&[], |x| x.revisions.as_slice())
+    }
+
+    fn selected_revision(&self) -> Option<&KeyValue> {
+        self.list_state
+            .selected()
+            .and_then(|x| self.revisions().get(x))
+    }
+
+    fn reload_history(&mut self) {
+        let key = self.key.clone();
+        self.marked = None;
+        self.load_history_task
+            .start(|s| async move { s.load_key_history(&key).await });
+    }
+
+    fn toggle_mark(&mut self) {
+        let selected = self.list_state.selected();
+        self.marked = if self.marked == selected {
+            None
+        } else {
+            selected
+        };
+    }
+
+    /// Show diff between marked and selected revisions or, if nothing marked, between selected
+    /// revision and the one before it.
+    fn show_diff(&mut self) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        let other = self.marked.unwrap_or(selected + 1);
+        // revisions are sorted from newest to oldest
+        let (new, old) = (min(selected, other), max(selected, other));
+
+        let revisions = self.revisions();
+        let Some(new) = revisions.get(new) else {
+            return;
+        };
+        let (old_title, old_value) = match revisions.get(old) {
+            Some(x) if x.is_deleted() => (format!("{} (deleted)", x.mod_revision), ""),
+            Some(x) => (x.mod_revision.to_string(), x.value.as_str()),
+            None => ("none".into(), ""),
+        };
+
+        let mut popup = DiffPopup::view_only(
+            format!("Diff of revisions {old_title} and {}", new.mod_revision),
+            old_value,
+            &new.value,
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.diff_popup = Some(popup);
+    }
+
+    fn prompt_restore(&mut self) {
+        if let Some(revision) = self.selected_revision() {
+            if revision.is_deleted() {
+                self.show_message("Cannot restore", "Selected revision is deletion of key");
+                return;
+            }
+            let mut popup = ConfirmationPopup::new(
+                format!("Restore revision {}?", revision.mod_revision),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.restore_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn restore_selected_revision(&mut self) {
+        let (Some(selected), Some(latest)) = (self.selected_revision(), self.revisions().first())
+        else {
+            return;
+        };
+        let key = self.key.clone();
+        let value = selected.value.clone();
+        // deleted key is expected to not exist
+        let mod_revision = if latest.is_deleted() {
+            0
+        } else {
+            latest.mod_revision
+        };
+        self.restore_task.start(move |s| async move {
+            s.put_key(&key, value, mod_revision, PutLease::None).await
+        });
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+}
+
+impl Component for HistoryBrowser {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_history_task.handle_key_event(event));
+            key_event!(self.restore_task.handle_key_event(event));
+            if let Some(ref mut x) = self.restore_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.diff_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(
+                            x.saturating_add(1),
+                            self.revisions().len().saturating_sub(1),
+                        )
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Char('m' | ' '),
+                    ..
+                } => {
+                    self.toggle_mark();
+                }
+                Input {
+                    key: Key::Char('d'),
+                    ..
+                } => {
+                    self.show_diff();
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } if self.shared_state.historical_revision().is_none() => {
+                    self.prompt_restore();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_history();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::KeyHistoryDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_history_task.try_ready() {
+            match result {
+                Ok(history) => {
+                    let selected = (!history.revisions.is_empty()).then_some(0);
+                    self.list_state.select(selected);
+                    self.history = Some(history);
+                }
+                Err(err) => self.show_message("Cannot load history", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.restore_task.try_ready() {
+            match result {
+                Ok(PutResult::Done) => {}
+                Ok(PutResult::Conflict(_)) => self.show_message(
+                    "Conflict",
+                    "Key was modified after history was loaded, nothing restored",
+                ),
+                Err(err) => self.show_message("Cannot restore revision", &err.to_string()),
+            }
+            self.reload_history();
+        }
+
+        if let Some(ref mut x) = self.restore_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.restore_selected_revision();
+                }
+                self.restore_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.diff_popup {
+            if x.status().is_some() {
+                self.diff_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(30), Constraint::Min(0)])
+                .split(rect);
+
+            let mut items = self
+                .revisions()
+                .iter()
+                .enumerate()
+                .map(|(idx, x)| {
+                    let mark = if self.marked == Some(idx) { "*" } else { " " };
+                    if x.is_deleted() {
+                        ListItem::new(format!("{mark} rev {} (deleted)", x.mod_revision))
+                    } else {
+                        ListItem::new(format!(
+                            "{mark} rev {} (version {})",
+                            x.mod_revision, x.version
+                        ))
+                    }
+                })
+                .collect::<Vec<_>>();
+            if self.history.as_ref().is_some_and(|x| x.is_truncated) {
+                items.push(ListItem::new("  (older revisions omitted)"));
+            } else if self.history.as_ref().is_some_and(|x| x.is_compacted) {
+                items.push(ListItem::new("  (older revisions compacted)"));
+            }
+
+            let list_widget = List::new(items)
+                .block(main_titled_block(format!("Key '{}' history", self.key)))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (value_title, value) = match self.selected_revision() {
+                Some(x) if x.is_deleted() => (
+                    format!("Revision {} (deleted)", x.mod_revision),
+                    String::new(),
+                ),
+                Some(x) => (
+                    format!(
+                        "Revision {} (version {}, created at {})",
+                        x.mod_revision, x.version, x.create_revision
+                    ),
+                    x.value.clone(),
+                ),
+                None => ("Revision".into(), String::new()),
+            };
+            let value_widget = Paragraph::new(value).block(main_titled_block(value_title));
+            frame.render_widget(value_widget, layout[1]);
+
+            self.load_history_task.draw(frame, rect);
+            self.restore_task.draw(frame, rect);
+            if let Some(ref mut x) = self.restore_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.diff_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_history_task.is_visible() {
+                return self.load_history_task.context_help();
+            }
+
+            if self.restore_task.is_visible() {
+                return self.restore_task.context_help();
+            }
+
+            if let Some(ref x) = self.restore_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.diff_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            let mut help = vec![
+                "(Up/Down) scroll list".into(),
+                "(m/Space) mark revision".into(),
+                "(d) diff with marked or previous revision".into(),
+            ];
+            if self.shared_state.historical_revision().is_none() {
+                help.push("(r) restore revision".into());
+            }
+            help.extend(["(R) reload".into(), "(Esc) return to key selection".into()]);
+            help
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::Rect,
+    style::{Style, Stylize},
+    widgets::{Borders, Clear},
+};
+use tui_textarea::{CursorMove, Input, Key, TextArea};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::SharedState,
+    ui::{calculate_center_rect, titled_block, Frame},
+};
+
+use super::Component;
+
+#[derive(Clone, Debug)]
+pub enum InputResult {
+    Cancel,
+    Done(String),
+}
+
+impl InputResult {
+    pub fn into_done(self) -> Option<String> {
+        if let Self::Done(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+}
+
+/// Single line input popup.
+pub struct InputPopup {
+    title: String,
+    textarea: TextArea<'static>,
+    result: Option<InputResult>,
+    /// Whether input is masked, e.g. for passwords.
+    is_hidden: bool,
+    /// Whether empty input can be submitted, otherwise Enter is ignored until some text is
+    /// entered.
+    allows_empty: bool,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl InputPopup {
+    pub fn new(title: impl ToString, placeholder: &str, shared_state: SharedState) -> Self {
+        let mut textarea = TextArea::default();
+        textarea.set_cursor_line_style(Style::default());
+        textarea.set_placeholder_text(placeholder);
+
+        Self {
+            title: title.to_string(),
+            textarea,
+            result: None,
+            is_hidden: false,
+            allows_empty: false,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    pub fn with_value(mut self, value: &str) -> Self {
+        self.textarea.insert_str(value);
+        self
+    }
+
+    pub fn with_hidden_input(mut self) -> Self {
+        self.is_hidden = true;
+        self
+    }
+
+    pub fn with_empty_input_allowed(mut self) -> Self {
+        self.allows_empty = true;
+        self
+    }
+
+    fn set_done(&mut self) -> Result<()> {
+        if let Some(x) = self.textarea.lines().first().cloned() {
+            if !x.is_empty() || self.allows_empty {
+                self.result = Some(InputResult::Done(x));
+                self.shared_state.tick()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> Option<InputResult> {
+        self.result.clone()
+    }
+}
+
+impl Component for InputPopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            match event.into() {
+                Input { key: Key::Esc, .. } => {
+                    self.result = Some(InputResult::Cancel);
+                    self.shared_state.tick()?;
+                }
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    self.set_done()?;
+                }
+                input => {
+                    self.textarea.input(input);
+                }
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        let block = titled_block(self.title.clone())
+            .borders(Borders::ALL)
+            .on_dark_gray();
+        let rect = calculate_center_rect(40, 3, frame.size());
+        frame.render_widget(Clear, rect);
+
+        if self.is_hidden {
+            // draw masked copy of input, keeping cursor position
+            let masked = self
+                .textarea
+                .lines()
+                .iter()
+                .map(|x| "*".repeat(x.chars().count()))
+                .collect();
+            let (row, col) = self.textarea.cursor();
+            let mut textarea = TextArea::new(masked);
+            textarea.set_cursor_line_style(Style::default());
+            textarea.set_block(block);
+            textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+            frame.render_widget(textarea.widget(), rect);
+        } else {
+            self.textarea.set_block(block);
+            frame.render_widget(self.textarea.widget(), rect);
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            vec!["(Enter) done".into(), "(Esc) cancel".into()]
+        } else {
+            vec![]
+        }
+    }
+}
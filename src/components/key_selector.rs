@@ -4,8 +4,9 @@ use anyhow::Result;
 
 use crossterm::event::KeyEvent;
 use ratatui::{
-    prelude::Rect,
-    style::{Modifier, Style},
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
     widgets::{List, ListItem, ListState},
 };
 
@@ -13,11 +14,17 @@ use tui_textarea::{Input, Key};
 
 use crate::{
     events::{Event, KeyEventState},
+    permission::EffectivePermissions,
+    shared_state::{CommitResult, KeyChange, KeyValue, PutResult},
     ui::{main_titled_block, Frame},
+    utils::AsyncTask,
     SharedState,
 };
 
-use super::{Component, ConfirmationPopup, ForegroundTask, NewKeyPopup};
+use super::{
+    Component, ConfirmationPopup, ForegroundTask, InputPopup, KeyspaceDiffPopup, MessagePopup,
+    NewKeyPopup,
+};
 
 pub struct KeySelector {
     shared_state: SharedState,
@@ -26,12 +33,30 @@ pub struct KeySelector {
 
     keys: Vec<String>,
     list_state: ListState,
+    /// Key to select, once key list is loaded.
+    key_to_reveal: Option<String>,
+    /// Permissions of current user, `None` until they are loaded.
+    permissions: Option<EffectivePermissions>,
+    load_permissions_task: AsyncTask<Result<EffectivePermissions>>,
 
-    get_key_task: ForegroundTask<Result<(String, String)>>,
+    get_key_task: ForegroundTask<Result<(String, KeyValue)>>,
     load_key_list_task: ForegroundTask<Result<Vec<String>>>,
     delete_key_task: ForegroundTask<Result<()>>,
     delete_key_confirmation_popup: Option<ConfirmationPopup>,
     new_key_popup: Option<NewKeyPopup>,
+    time_travel_popup: Option<InputPopup>,
+    watch_popup: Option<InputPopup>,
+    time_travel_task: ForegroundTask<Result<i64>>,
+    keyspace_diff_task: ForegroundTask<Result<(i64, Vec<KeyChange>)>>,
+    keyspace_diff_popup: Option<KeyspaceDiffPopup>,
+    message_popup: Option<MessagePopup>,
+    ephemeral_key_popup: Option<InputPopup>,
+    /// Popup for value of ephemeral key, along with the key.
+    ephemeral_value_popup: Option<(String, InputPopup)>,
+    put_ephemeral_key_task: ForegroundTask<Result<PutResult>>,
+    rename_popup: Option<InputPopup>,
+    /// Result of rename, `None` if it was staged.
+    rename_task: ForegroundTask<Result<Option<CommitResult>>>,
 }
 
 impl KeySelector {
@@ -43,15 +68,56 @@ impl KeySelector {
 
             keys: vec![],
             list_state: ListState::default(),
+            key_to_reveal: None,
+            permissions: None,
+            load_permissions_task: AsyncTask::new(shared_state.clone()),
 
             get_key_task: ForegroundTask::new("Loading key", shared_state.clone()),
             load_key_list_task: ForegroundTask::new("Loading key list", shared_state.clone()),
             delete_key_task: ForegroundTask::new("Deleting key list", shared_state.clone()),
             delete_key_confirmation_popup: None,
             new_key_popup: None,
+            time_travel_popup: None,
+            watch_popup: None,
+            time_travel_task: ForegroundTask::new("Checking revision", shared_state.clone()),
+            keyspace_diff_task: ForegroundTask::new("Comparing keyspace", shared_state.clone()),
+            keyspace_diff_popup: None,
+            message_popup: None,
+            ephemeral_key_popup: None,
+            ephemeral_value_popup: None,
+            put_ephemeral_key_task: ForegroundTask::new(
+                "Creating ephemeral key",
+                shared_state.clone(),
+            ),
+            rename_popup: None,
+            rename_task: ForegroundTask::new("Renaming key", shared_state),
         }
     }
 
+    /// Show key selector with `key` selected.
+    pub fn reveal_key(&mut self, key: String) {
+        self.key_to_reveal = Some(key);
+        self.show();
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.shared_state.historical_revision().is_some()
+    }
+
+    /// Whether current user can read selected key, but not write it.
+    fn is_selected_key_read_only(&self) -> bool {
+        self.list_state
+            .selected()
+            .and_then(|x| self.keys.get(x))
+            .is_some_and(|x| self.is_key_read_only(x))
+    }
+
+    fn is_key_read_only(&self, key: &str) -> bool {
+        self.permissions
+            .as_ref()
+            .is_some_and(|x| x.is_read_only(key))
+    }
+
     fn selected_list_item(&self) -> Option<String> {
         self.list_state
             .selected()
@@ -71,19 +137,33 @@ impl KeySelector {
     fn reload_keys(&mut self) {
         self.load_key_list_task
             .start(|s| async move { s.load_keys().await });
+        // permissions can't be loaded when replaying recorded events
+        if self.shared_state.cli().replay.is_none() && !self.load_permissions_task.is_active() {
+            self.load_permissions_task
+                .start(|s| async move { s.load_effective_permissions().await });
+        }
     }
 
     fn delete_key(&mut self) {
         if let Some(key) = self.selected_list_item() {
-            self.delete_key_task
-                .start(|s| async move { s.delete_key(&key).await });
+            if self.shared_state.is_staging() {
+                self.delete_key_task
+                    .start(|s| async move { s.stage_delete(&key).await });
+            } else {
+                self.delete_key_task
+                    .start(|s| async move { s.delete_key(&key).await });
+            }
         }
     }
 
     fn prompt_key_delete(&mut self) {
         if let Some(key) = self.selected_list_item() {
-            let mut popup =
-                ConfirmationPopup::new(format!("Delete key '{key}'?"), self.shared_state.clone());
+            let description = if self.shared_state.is_staging() {
+                format!("Stage deletion of key '{key}'?")
+            } else {
+                format!("Delete key '{key}'?")
+            };
+            let mut popup = ConfirmationPopup::new(description, self.shared_state.clone());
             popup.show();
             self.delete_key_confirmation_popup = Some(popup);
         }
@@ -96,6 +176,141 @@ impl KeySelector {
             self.new_key_popup = Some(popup);
         }
     }
+
+    /// Enter time-travel mode, or leave it, if already entered.
+    fn toggle_time_travel(&mut self) {
+        if self.is_read_only() {
+            self.shared_state.set_historical_revision(None);
+            self.reload_keys();
+        } else {
+            let mut popup =
+                InputPopup::new("Time travel", "Enter revision", self.shared_state.clone());
+            popup.show();
+            self.time_travel_popup = Some(popup);
+        }
+    }
+
+    fn start_time_travel(&mut self, revision: &str) {
+        let revision = match revision.trim().parse::<i64>() {
+            Ok(x) if x > 0 => x,
+            _ => {
+                self.show_message("Error", &format!("Invalid revision '{revision}'"));
+                return;
+            }
+        };
+        self.time_travel_task.start(move |s| async move {
+            s.check_revision(revision).await?;
+            Ok(revision)
+        });
+    }
+
+    fn diff_keyspace(&mut self) {
+        if let Some(revision) = self.shared_state.historical_revision() {
+            self.keyspace_diff_task.start(move |s| async move {
+                let changes = s.diff_keyspace(revision).await?;
+                Ok((revision, changes))
+            });
+        }
+    }
+
+    fn prompt_watch(&mut self) {
+        let mut popup = InputPopup::new("Watch", "Enter key prefix", self.shared_state.clone())
+            .with_value(&self.selected_list_item().unwrap_or_default());
+        popup.show();
+        self.watch_popup = Some(popup);
+    }
+
+    fn toggle_staging(&mut self) {
+        let is_staging = self.shared_state.is_staging();
+        if is_staging && !self.shared_state.staged_changes().is_empty() {
+            self.show_message(
+                "Staging",
+                "Commit or discard staged changes before leaving staging mode",
+            );
+        } else {
+            self.shared_state.set_staging(!is_staging);
+        }
+    }
+
+    fn prompt_rename(&mut self) {
+        if let Some(key) = self.selected_list_item() {
+            let mut popup = InputPopup::new(
+                format!("Rename '{key}' to"),
+                "Enter new key name",
+                self.shared_state.clone(),
+            )
+            .with_value(&key);
+            popup.show();
+            self.rename_popup = Some(popup);
+        }
+    }
+
+    fn rename_selected_key(&mut self, to: String) {
+        let Some(from) = self.selected_list_item() else {
+            return;
+        };
+        if self.shared_state.is_staging() {
+            self.rename_task.start(|s| async move {
+                s.stage_rename(&from, &to).await?;
+                Ok(None)
+            });
+        } else {
+            self.rename_task
+                .start(|s| async move { Ok(Some(s.rename_key(&from, &to).await?)) });
+        }
+    }
+
+    fn prompt_ephemeral_key(&mut self) {
+        let mut popup = InputPopup::new(
+            "Ephemeral key (deleted on exit)",
+            "Enter key name",
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.ephemeral_key_popup = Some(popup);
+    }
+
+    fn prompt_ephemeral_value(&mut self, key: String) {
+        let mut popup = InputPopup::new(
+            format!("Value of '{key}'"),
+            "Enter value, may be empty",
+            self.shared_state.clone(),
+        )
+        .with_empty_input_allowed();
+        popup.show();
+        self.ephemeral_value_popup = Some((key, popup));
+    }
+
+    fn put_ephemeral_key(&mut self, key: String, value: String) {
+        self.put_ephemeral_key_task
+            .start(move |s| async move { s.put_ephemeral_key(&key, value).await });
+    }
+
+    fn draw_ephemeral_keys(&self, frame: &mut Frame, rect: Rect) {
+        let items = self
+            .shared_state
+            .ephemeral_keys()
+            .into_iter()
+            .map(|x| {
+                if self.is_read_only() || self.keys.contains(&x) {
+                    ListItem::new(x)
+                } else {
+                    ListItem::new(Line::styled(
+                        format!("{x} (deleted)"),
+                        Style::default().dark_gray(),
+                    ))
+                }
+            })
+            .collect::<Vec<_>>();
+        let widget = List::new(items).block(main_titled_block("Ephemeral keys (deleted on exit)"));
+        frame.render_widget(widget, rect);
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
 }
 
 impl Component for KeySelector {
@@ -110,6 +325,31 @@ impl Component for KeySelector {
             if let Some(ref mut x) = self.new_key_popup {
                 key_event!(x.handle_key_event(event));
             }
+            key_event!(self.time_travel_task.handle_key_event(event));
+            key_event!(self.keyspace_diff_task.handle_key_event(event));
+            if let Some(ref mut x) = self.time_travel_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.watch_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.keyspace_diff_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.ephemeral_key_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some((_, ref mut x)) = self.ephemeral_value_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.put_ephemeral_key_task.handle_key_event(event));
+            if let Some(ref mut x) = self.rename_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.rename_task.handle_key_event(event));
 
             let selected_key_id = self.list_state.selected();
             match event.into() {
@@ -132,15 +372,117 @@ impl Component for KeySelector {
                 Input {
                     key: Key::Char('n'),
                     ..
-                } => {
+                } if !self.is_read_only() => {
                     self.prompt_new_key();
                 }
                 Input {
-                    key: Key::Delete | Key::Char('d'),
+                    key: Key::Char('N'),
+                    ..
+                } if !self.is_read_only() => {
+                    self.prompt_ephemeral_key();
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } if !self.is_read_only() && !self.is_selected_key_read_only() => {
+                    self.prompt_rename();
+                }
+                Input {
+                    key: Key::Char('s'),
+                    ..
+                } if !self.is_read_only() => {
+                    self.toggle_staging();
+                }
+                Input {
+                    key: Key::Char('S'),
+                    ..
+                } if self.shared_state.is_staging() => {
+                    self.shared_state.send_event(Event::ShowStagingReview)?;
+                }
+                Input {
+                    key: Key::Char('h'),
                     ..
                 } => {
+                    if let Some(key) = self.selected_list_item() {
+                        self.shared_state.send_event(Event::ShowKeyHistory(key))?;
+                    }
+                }
+                Input {
+                    key: Key::Delete | Key::Char('d'),
+                    ..
+                } if !self.is_read_only() && !self.is_selected_key_read_only() => {
                     self.prompt_key_delete();
                 }
+                Input {
+                    key: Key::Char('w'),
+                    ..
+                } => {
+                    self.prompt_watch();
+                }
+                Input {
+                    key: Key::Char('t'),
+                    ..
+                } => {
+                    self.toggle_time_travel();
+                }
+                Input {
+                    key: Key::Char('D'),
+                    ..
+                } => {
+                    self.diff_keyspace();
+                }
+                Input {
+                    key: Key::Char('L'),
+                    ..
+                } => {
+                    self.shared_state.send_event(Event::ShowLeases)?;
+                }
+                Input {
+                    key: Key::Char('C'),
+                    ..
+                } => {
+                    let key = self.selected_list_item();
+                    self.shared_state.send_event(Event::ShowCluster(key))?;
+                }
+                Input {
+                    key: Key::Char('A'),
+                    ..
+                } => {
+                    self.shared_state.send_event(Event::ShowAlarms)?;
+                }
+                Input {
+                    key: Key::Char('U'),
+                    ..
+                } => {
+                    self.shared_state.send_event(Event::ShowUsers)?;
+                }
+                Input {
+                    key: Key::Char('P'),
+                    ..
+                } => {
+                    let key = self.selected_list_item();
+                    self.shared_state.send_event(Event::ShowRoles(key))?;
+                }
+                Input {
+                    key: Key::Char('I'),
+                    ..
+                } => {
+                    let key = self.selected_list_item();
+                    self.shared_state.send_event(Event::ShowPermissions(key))?;
+                }
+                Input {
+                    key: Key::Char('K'),
+                    ..
+                } => {
+                    let key = self.selected_list_item();
+                    self.shared_state.send_event(Event::ShowLocks(key))?;
+                }
+                Input {
+                    key: Key::Char('T'),
+                    ..
+                } => {
+                    self.shared_state.send_event(Event::ShowTxnBuilder)?;
+                }
                 Input { key: Key::Esc, .. } => {
                     self.shared_state.send_event(Event::Quit(Ok(())))?;
                 }
@@ -155,11 +497,22 @@ impl Component for KeySelector {
     fn update(&mut self) -> Result<()> {
         if let Some(result) = self.load_key_list_task.try_ready() {
             self.keys = result?;
+            if let Some(key) = self.key_to_reveal.take() {
+                if let Some(idx) = self.keys.iter().position(|x| *x == key) {
+                    self.list_state.select(Some(idx));
+                }
+            }
+        }
+
+        if let Some(result) = self.load_permissions_task.try_ready() {
+            // keys are not marked as read-only, when permissions are unknown
+            self.permissions = result.ok();
         }
 
         if let Some(result) = self.get_key_task.try_ready() {
             let (key, value) = result?;
             let event = Event::KeySelected {
+                is_read_only: self.is_key_read_only(&key),
                 key,
                 value: Some(value),
             };
@@ -167,7 +520,29 @@ impl Component for KeySelector {
         }
 
         if let Some(result) = self.delete_key_task.try_ready() {
-            result?;
+            if let Err(err) = result {
+                self.show_message("Cannot delete key", &err.to_string());
+            }
+            self.reload_keys();
+        }
+
+        if let Some(ref mut x) = self.rename_popup {
+            if let Some(result) = x.status() {
+                self.rename_popup = None;
+                if let Some(to) = result.into_done() {
+                    self.rename_selected_key(to);
+                }
+            }
+        }
+
+        if let Some(result) = self.rename_task.try_ready() {
+            match result {
+                Ok(Some(CommitResult::Conflict(_))) => {
+                    self.show_message("Conflict", "Key was modified while renaming it")
+                }
+                Ok(_) => {}
+                Err(err) => self.show_message("Cannot rename key", &err.to_string()),
+            }
             self.reload_keys();
         }
 
@@ -182,30 +557,155 @@ impl Component for KeySelector {
 
         if let Some(ref mut x) = self.new_key_popup {
             if let Some(result) = x.status() {
-                if let Some(key) = result.as_done() {
-                    let event = Event::KeySelected { key, value: None };
-                    self.shared_state.send_event(event)?;
+                if let Some((key, lease)) = result.into_done() {
+                    self.shared_state.send_event(Event::NewKey { key, lease })?;
                 }
                 self.new_key_popup = None;
             }
         }
 
+        if let Some(ref mut x) = self.time_travel_popup {
+            if let Some(result) = x.status() {
+                self.time_travel_popup = None;
+                if let Some(revision) = result.into_done() {
+                    self.start_time_travel(&revision);
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.watch_popup {
+            if let Some(result) = x.status() {
+                self.watch_popup = None;
+                if let Some(prefix) = result.into_done() {
+                    self.shared_state.send_event(Event::ShowWatch(prefix))?;
+                }
+            }
+        }
+
+        if let Some(result) = self.time_travel_task.try_ready() {
+            match result {
+                Ok(revision) => {
+                    self.shared_state.set_historical_revision(Some(revision));
+                    self.reload_keys();
+                }
+                Err(err) => self.show_message("Cannot travel to revision", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.keyspace_diff_task.try_ready() {
+            match result {
+                Ok((revision, changes)) => {
+                    let mut popup =
+                        KeyspaceDiffPopup::new(revision, changes, self.shared_state.clone());
+                    popup.show();
+                    self.keyspace_diff_popup = Some(popup);
+                }
+                Err(err) => self.show_message("Cannot compare keyspace", &err.to_string()),
+            }
+        }
+
+        if let Some(ref mut x) = self.keyspace_diff_popup {
+            x.update()?;
+            if x.is_closed() {
+                self.keyspace_diff_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.ephemeral_key_popup {
+            if let Some(result) = x.status() {
+                self.ephemeral_key_popup = None;
+                if let Some(key) = result.into_done() {
+                    self.prompt_ephemeral_value(key);
+                }
+            }
+        }
+
+        if let Some((_, ref mut x)) = self.ephemeral_value_popup {
+            if let Some(result) = x.status() {
+                let (key, _) = self.ephemeral_value_popup.take().expect("Popup exists");
+                if let Some(value) = result.into_done() {
+                    self.put_ephemeral_key(key, value);
+                }
+            }
+        }
+
+        if let Some(result) = self.put_ephemeral_key_task.try_ready() {
+            match result {
+                Ok(PutResult::Done) => self.reload_keys(),
+                Ok(PutResult::Conflict(_)) => self.show_message("Error", "Key already exists"),
+                Err(err) => self.show_message("Cannot create ephemeral key", &err.to_string()),
+            }
+        }
+
         Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
         if self.is_visible() {
+            let staged_changes = self.shared_state.staged_changes();
+            let permissions = self.permissions.as_ref().filter(|_| !self.is_read_only());
             let items = self
                 .keys
                 .iter()
-                .map(|x| ListItem::new(x.clone()))
+                .map(|x| {
+                    if staged_changes
+                        .iter()
+                        .any(|c| c.keys().contains(&x.as_str()))
+                    {
+                        ListItem::new(Line::styled(
+                            format!("{x} (staged)"),
+                            Style::default().cyan(),
+                        ))
+                    } else if permissions.is_some_and(|p| p.is_read_only(x)) {
+                        ListItem::new(Line::styled(
+                            format!("{x} (read-only)"),
+                            Style::default().dark_gray(),
+                        ))
+                    } else {
+                        ListItem::new(x.clone())
+                    }
+                })
                 .collect::<Vec<_>>();
 
+            let title = match self.shared_state.historical_revision() {
+                Some(x) => Line::styled(
+                    format!("Keys at revision {x} (historical, read-only)"),
+                    Style::default().yellow(),
+                ),
+                None if self.shared_state.is_staging() => Line::styled(
+                    format!("Keys (staging, {} change(s))", staged_changes.len()),
+                    Style::default().cyan(),
+                ),
+                None => Line::from("Keys"),
+            };
+
             let widget = List::new(items)
-                .block(main_titled_block("Keys"))
+                .block(main_titled_block(title))
                 .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
-            frame.render_stateful_widget(widget, rect, &mut self.list_state);
+            let ephemeral_keys_count = self.shared_state.ephemeral_keys().len() as u16;
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Min(0),
+                    Constraint::Length(if ephemeral_keys_count > 0 {
+                        ephemeral_keys_count.min(5) + 2
+                    } else {
+                        0
+                    }),
+                ])
+                .split(rect);
+
+            frame.render_stateful_widget(widget, layout[0], &mut self.list_state);
+            if ephemeral_keys_count > 0 {
+                self.draw_ephemeral_keys(frame, layout[1]);
+            }
 
             self.get_key_task.draw(frame, rect);
             self.load_key_list_task.draw(frame, rect);
@@ -216,6 +716,31 @@ impl Component for KeySelector {
             if let Some(ref mut x) = self.new_key_popup {
                 x.draw(frame, rect);
             }
+            self.time_travel_task.draw(frame, rect);
+            self.keyspace_diff_task.draw(frame, rect);
+            if let Some(ref mut x) = self.time_travel_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.watch_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.keyspace_diff_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.ephemeral_key_popup {
+                x.draw(frame, rect);
+            }
+            if let Some((_, ref mut x)) = self.ephemeral_value_popup {
+                x.draw(frame, rect);
+            }
+            self.put_ephemeral_key_task.draw(frame, rect);
+            if let Some(ref mut x) = self.rename_popup {
+                x.draw(frame, rect);
+            }
+            self.rename_task.draw(frame, rect);
         }
     }
 
@@ -241,13 +766,106 @@ impl Component for KeySelector {
                 return x.context_help();
             }
 
-            vec![
-                "(Up/Down) scroll list".into(),
-                "(e/Enter) select key".into(),
-                "(n) new key".into(),
-                "(d/Del) delete key".into(),
-                "(Esc) exit".into(),
-            ]
+            if self.time_travel_task.is_visible() {
+                return self.time_travel_task.context_help();
+            }
+
+            if self.keyspace_diff_task.is_visible() {
+                return self.keyspace_diff_task.context_help();
+            }
+
+            if let Some(ref x) = self.time_travel_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.watch_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.keyspace_diff_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.ephemeral_key_popup {
+                return x.context_help();
+            }
+
+            if let Some((_, ref x)) = self.ephemeral_value_popup {
+                return x.context_help();
+            }
+
+            if self.put_ephemeral_key_task.is_visible() {
+                return self.put_ephemeral_key_task.context_help();
+            }
+
+            if let Some(ref x) = self.rename_popup {
+                return x.context_help();
+            }
+
+            if self.rename_task.is_visible() {
+                return self.rename_task.context_help();
+            }
+
+            if self.is_read_only() {
+                vec![
+                    "(Up/Down) scroll list".into(),
+                    "(e/Enter) view key".into(),
+                    "(h) key history".into(),
+                    "(D) diff against current".into(),
+                    "(w) watch".into(),
+                    "(L) leases".into(),
+                    "(C) cluster".into(),
+                    "(A) alarms".into(),
+                    "(U) users".into(),
+                    "(P) roles".into(),
+                    "(I) permissions".into(),
+                    "(K) locks".into(),
+                    "(T) transaction (gets only)".into(),
+                    "(t) leave time travel".into(),
+                    "(Esc) exit".into(),
+                ]
+            } else {
+                let mut help: Vec<String> = vec!["(Up/Down) scroll list".into()];
+                if self.is_selected_key_read_only() {
+                    help.push("(e/Enter) view key".into());
+                } else {
+                    help.push("(e/Enter) select key".into());
+                }
+                help.extend([
+                    "(n) new key".into(),
+                    "(N) new ephemeral key".into(),
+                    "(h) key history".into(),
+                ]);
+                if !self.is_selected_key_read_only() {
+                    help.extend(["(d/Del) delete key".into(), "(r) rename key".into()]);
+                }
+                help.extend([
+                    "(w) watch".into(),
+                    "(L) leases".into(),
+                    "(C) cluster".into(),
+                    "(A) alarms".into(),
+                    "(U) users".into(),
+                    "(P) roles".into(),
+                    "(I) permissions".into(),
+                    "(K) locks".into(),
+                    "(T) transaction".into(),
+                    "(t) time travel".into(),
+                ]);
+                if self.shared_state.is_staging() {
+                    help.extend([
+                        "(S) review staged changes".into(),
+                        "(s) leave staging mode".into(),
+                    ]);
+                } else {
+                    help.push("(s) staging mode".into());
+                }
+                help.push("(Esc) exit".into());
+                help
+            }
         } else {
             vec![]
         }
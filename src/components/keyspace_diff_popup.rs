@@ -0,0 +1,181 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::Rect,
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Borders, Clear, List, ListItem, ListState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::{KeyChange, SharedState},
+    ui::{calculate_center_rect, titled_block, Frame},
+};
+
+use super::{Component, DiffPopup};
+
+/// Popup, listing keys changed since some revision.
+pub struct KeyspaceDiffPopup {
+    revision: i64,
+    changes: Vec<KeyChange>,
+    list_state: ListState,
+    diff_popup: Option<DiffPopup>,
+    is_closed: bool,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl KeyspaceDiffPopup {
+    pub fn new(revision: i64, changes: Vec<KeyChange>, shared_state: SharedState) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select((!changes.is_empty()).then_some(0));
+
+        Self {
+            revision,
+            changes,
+            list_state,
+            diff_popup: None,
+            is_closed: false,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
+    fn show_value_diff(&mut self) {
+        let Some(change) = self.list_state.selected().and_then(|x| self.changes.get(x)) else {
+            return;
+        };
+        let mut popup = DiffPopup::view_only(
+            format!("Key '{}'", change.key),
+            change.old_value.as_deref().unwrap_or_default(),
+            change.new_value.as_deref().unwrap_or_default(),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.diff_popup = Some(popup);
+    }
+}
+
+impl Component for KeyspaceDiffPopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            if let Some(ref mut x) = self.diff_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(x.saturating_add(1), self.changes.len().saturating_sub(1))
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    self.show_value_diff();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.is_closed = true;
+                    self.shared_state.tick()?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(ref mut x) = self.diff_popup {
+            if x.status().is_some() {
+                self.diff_popup = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        let block = titled_block(format!("Changes since revision {}", self.revision))
+            .borders(Borders::ALL)
+            .on_dark_gray();
+
+        let frame_rect = frame.size();
+        let popup_rect = calculate_center_rect(
+            frame_rect.width.saturating_mul(4) / 5,
+            frame_rect.height.saturating_mul(4) / 5,
+            frame_rect,
+        );
+
+        let items = if self.changes.is_empty() {
+            vec![ListItem::new(Line::styled(
+                "No changes",
+                Style::default().italic(),
+            ))]
+        } else {
+            self.changes
+                .iter()
+                .map(|x| {
+                    let line = match (&x.old_value, &x.new_value) {
+                        (None, _) => Line::styled(format!("+ {}", x.key), Style::default().green()),
+                        (_, None) => Line::styled(format!("- {}", x.key), Style::default().red()),
+                        _ => Line::styled(format!("~ {}", x.key), Style::default().yellow()),
+                    };
+                    ListItem::new(line)
+                })
+                .collect()
+        };
+
+        let widget = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_widget(Clear, popup_rect);
+        frame.render_stateful_widget(widget, popup_rect, &mut self.list_state);
+
+        if let Some(ref mut x) = self.diff_popup {
+            x.draw(frame, rect);
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if let Some(ref x) = self.diff_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(Enter) show value diff".into(),
+                "(Esc) close".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+}
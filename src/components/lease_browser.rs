@@ -0,0 +1,396 @@
+use std::{
+    cmp::min,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::Lease,
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, MessagePopup};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Pane {
+    Leases,
+    Keys,
+}
+
+/// Browser of leases and keys attached to them.
+pub struct LeaseBrowser {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    leases: Vec<Lease>,
+    /// Time, when leases were loaded, used to count down their TTL.
+    loaded_at: Instant,
+    list_state: ListState,
+    keys_list_state: ListState,
+    focused_pane: Pane,
+
+    load_leases_task: ForegroundTask<Result<Vec<Lease>>>,
+    revoke_task: ForegroundTask<Result<()>>,
+    keep_alive_task: ForegroundTask<Result<i64>>,
+    revoke_confirmation_popup: Option<ConfirmationPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl LeaseBrowser {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            leases: vec![],
+            loaded_at: Instant::now(),
+            list_state: ListState::default(),
+            keys_list_state: ListState::default(),
+            focused_pane: Pane::Leases,
+
+            load_leases_task: ForegroundTask::new("Loading leases", shared_state.clone()),
+            revoke_task: ForegroundTask::new("Revoking lease", shared_state.clone()),
+            keep_alive_task: ForegroundTask::new("Renewing lease", shared_state),
+            revoke_confirmation_popup: None,
+            message_popup: None,
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.shared_state.historical_revision().is_some()
+    }
+
+    fn selected_lease(&self) -> Option<&Lease> {
+        self.list_state.selected().and_then(|x| self.leases.get(x))
+    }
+
+    fn selected_key(&self) -> Option<&String> {
+        let lease = self.selected_lease()?;
+        self.keys_list_state
+            .selected()
+            .and_then(|x| lease.keys.get(x))
+    }
+
+    /// Remaining TTL of lease, counted down since leases were loaded.
+    fn remaining_ttl(&self, lease: &Lease) -> Duration {
+        Duration::from_secs(lease.ttl.max(0) as u64).saturating_sub(self.loaded_at.elapsed())
+    }
+
+    fn reload_leases(&mut self) {
+        self.load_leases_task
+            .start(|s| async move { s.load_leases().await });
+    }
+
+    fn select_lease(&mut self, idx: Option<usize>) {
+        self.list_state.select(idx);
+        let has_keys = self.selected_lease().is_some_and(|x| !x.keys.is_empty());
+        self.keys_list_state.select(has_keys.then_some(0));
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let (list_state, len) = match self.focused_pane {
+            Pane::Leases => (&self.list_state, self.leases.len()),
+            Pane::Keys => (
+                &self.keys_list_state,
+                self.selected_lease().map_or(0, |x| x.keys.len()),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        let selected = list_state.selected().map_or(0, |x| {
+            if down {
+                min(x.saturating_add(1), len - 1)
+            } else {
+                x.saturating_sub(1)
+            }
+        });
+        match self.focused_pane {
+            Pane::Leases => self.select_lease(Some(selected)),
+            Pane::Keys => self.keys_list_state.select(Some(selected)),
+        }
+    }
+
+    fn toggle_focused_pane(&mut self) {
+        self.focused_pane = match self.focused_pane {
+            Pane::Leases => Pane::Keys,
+            Pane::Keys => Pane::Leases,
+        };
+    }
+
+    fn prompt_revoke(&mut self) {
+        if let Some(lease) = self.selected_lease() {
+            let mut popup = ConfirmationPopup::new(
+                format!(
+                    "Revoke lease {:#x}? {} attached key(s) will be deleted.",
+                    lease.id,
+                    lease.keys.len()
+                ),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.revoke_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn revoke_selected_lease(&mut self) {
+        if let Some(id) = self.selected_lease().map(|x| x.id) {
+            self.revoke_task
+                .start(move |s| async move { s.revoke_lease(id).await });
+        }
+    }
+
+    fn keep_alive_selected_lease(&mut self) {
+        if let Some(id) = self.selected_lease().map(|x| x.id) {
+            self.keep_alive_task
+                .start(move |s| async move { s.keep_alive_lease(id).await });
+        }
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+}
+
+impl Component for LeaseBrowser {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_leases_task.handle_key_event(event));
+            key_event!(self.revoke_task.handle_key_event(event));
+            key_event!(self.keep_alive_task.handle_key_event(event));
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.move_selection(true);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.move_selection(false);
+                }
+                Input {
+                    key: Key::Tab | Key::Left | Key::Right,
+                    ..
+                } => {
+                    self.toggle_focused_pane();
+                }
+                Input {
+                    key: Key::Enter, ..
+                } if self.focused_pane == Pane::Keys => {
+                    if let Some(key) = self.selected_key().cloned() {
+                        self.shared_state.send_event(Event::RevealKey(key))?;
+                    }
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } if !self.is_read_only() => {
+                    self.prompt_revoke();
+                }
+                Input {
+                    key: Key::Char('k'),
+                    ..
+                } if !self.is_read_only() => {
+                    self.keep_alive_selected_lease();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_leases();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::LeasesDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_leases_task.try_ready() {
+            match result {
+                Ok(leases) => {
+                    let selected_id = self.selected_lease().map(|x| x.id);
+                    self.leases = leases;
+                    self.loaded_at = Instant::now();
+                    let idx = selected_id
+                        .and_then(|id| self.leases.iter().position(|x| x.id == id))
+                        .or((!self.leases.is_empty()).then_some(0));
+                    self.select_lease(idx);
+                }
+                // previously loaded leases are kept
+                Err(err) => self.show_message("Cannot load leases", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.revoke_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot revoke lease", &err.to_string());
+            }
+            self.reload_leases();
+        }
+
+        if let Some(result) = self.keep_alive_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot renew lease", &err.to_string());
+            }
+            self.reload_leases();
+        }
+
+        if let Some(ref mut x) = self.revoke_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.revoke_selected_lease();
+                }
+                self.revoke_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(40), Constraint::Min(0)])
+                .split(rect);
+
+            let items = self
+                .leases
+                .iter()
+                .map(|x| {
+                    let ttl = self.remaining_ttl(x).as_secs();
+                    let text = format!(
+                        "{:#x}  TTL {ttl}/{}s, {} key(s)",
+                        x.id,
+                        x.granted_ttl,
+                        x.keys.len()
+                    );
+                    if ttl == 0 {
+                        ListItem::new(Line::styled(text, Style::default().dark_gray()))
+                    } else {
+                        ListItem::new(text)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let highlight_style = |pane| {
+                if self.focused_pane == pane {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                }
+            };
+
+            let list_widget = List::new(items)
+                .block(main_titled_block("Leases"))
+                .highlight_style(highlight_style(Pane::Leases));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (keys_title, keys) = match self.selected_lease() {
+                Some(x) => (
+                    format!("Keys attached to lease {:#x}", x.id),
+                    x.keys.iter().map(|x| ListItem::new(x.clone())).collect(),
+                ),
+                None => ("Keys".into(), vec![]),
+            };
+            let keys_widget = List::new(keys)
+                .block(main_titled_block(keys_title))
+                .highlight_style(highlight_style(Pane::Keys));
+            frame.render_stateful_widget(keys_widget, layout[1], &mut self.keys_list_state);
+
+            self.load_leases_task.draw(frame, rect);
+            self.revoke_task.draw(frame, rect);
+            self.keep_alive_task.draw(frame, rect);
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_leases_task.is_visible() {
+                return self.load_leases_task.context_help();
+            }
+
+            if self.revoke_task.is_visible() {
+                return self.revoke_task.context_help();
+            }
+
+            if self.keep_alive_task.is_visible() {
+                return self.keep_alive_task.context_help();
+            }
+
+            if let Some(ref x) = self.revoke_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            let mut help = vec![
+                "(Up/Down) scroll list".into(),
+                "(Tab) switch between leases and keys".into(),
+            ];
+            if self.focused_pane == Pane::Keys {
+                help.push("(Enter) go to key".into());
+            }
+            if !self.is_read_only() {
+                help.extend(["(r) revoke lease".into(), "(k) keep alive once".into()]);
+            }
+            help.extend(["(R) reload".into(), "(Esc) return to key selection".into()]);
+            help
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.focused_pane = Pane::Leases;
+        self.reload_leases();
+    }
+}
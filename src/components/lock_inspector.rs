@@ -0,0 +1,532 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Cell, Paragraph, Row, Table, TableState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::LockWaiter,
+    ui::{main_titled_block, Frame},
+    utils::AsyncTask,
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, InputPopup, MessagePopup};
+
+/// Interval of reloading waiters of lock.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Waiters and holder of lock, created with etcd lock API.
+pub struct LockInspector {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    /// Name of lock, which is prefix of keys of its waiters.
+    name: Option<String>,
+    waiters: Vec<LockWaiter>,
+    table_state: TableState,
+    error: Option<String>,
+    refreshed_at: Option<Instant>,
+    refresh_task: AsyncTask<Result<Vec<LockWaiter>>>,
+
+    name_popup: Option<InputPopup>,
+    /// Acquisition of lock for testing, which runs in background until lock is acquired.
+    acquire_task: AsyncTask<Result<String>>,
+    release_task: ForegroundTask<Result<()>>,
+    /// Holder, which is going to be force-unlocked.
+    force_unlock_holder: Option<LockWaiter>,
+    force_unlock_confirmation_popup: Option<ConfirmationPopup>,
+    force_unlock_task: ForegroundTask<Result<()>>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl LockInspector {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            name: None,
+            waiters: vec![],
+            table_state: TableState::default(),
+            error: None,
+            refreshed_at: None,
+            refresh_task: AsyncTask::new(shared_state.clone()),
+
+            name_popup: None,
+            acquire_task: AsyncTask::new(shared_state.clone()),
+            release_task: ForegroundTask::new("Releasing lock", shared_state.clone()),
+            force_unlock_holder: None,
+            force_unlock_confirmation_popup: None,
+            force_unlock_task: ForegroundTask::new("Revoking lease of holder", shared_state),
+            message_popup: None,
+        }
+    }
+
+    /// Show locks, guessing name of lock from key selected in key selection, if any, as its
+    /// waiters are `<name>/<lease ID>`.
+    pub fn open(&mut self, selected_key: Option<String>) {
+        let name = selected_key
+            .as_deref()
+            .and_then(|x| x.rsplit_once('/'))
+            .map(|(name, _)| name.to_string())
+            .filter(|x| !x.is_empty());
+        if name.is_some() {
+            self.set_name(name);
+        }
+        self.show();
+    }
+
+    fn set_name(&mut self, name: Option<String>) {
+        if self.name != name {
+            self.name = name;
+            self.waiters = vec![];
+            self.table_state.select(None);
+            self.error = None;
+            self.refresh_task.abort();
+        }
+        self.refreshed_at = None;
+    }
+
+    fn refresh(&mut self) {
+        let Some(name) = self.name.clone() else {
+            return;
+        };
+        if !self.refresh_task.is_active() {
+            self.refresh_task
+                .start(|s| async move { s.load_lock_waiters(&name).await });
+        }
+    }
+
+    fn selected_waiter(&self) -> Option<&LockWaiter> {
+        self.table_state
+            .selected()
+            .and_then(|x| self.waiters.get(x))
+    }
+
+    /// Waiter, which was created by this app for testing.
+    fn own_waiter(&self) -> Option<&LockWaiter> {
+        self.waiters
+            .iter()
+            .find(|x| x.lease != 0 && self.shared_state.is_session_lease(x.lease))
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        if self.waiters.is_empty() {
+            return;
+        }
+        let selected = self.table_state.selected().map_or(0, |x| {
+            if down {
+                (x + 1).min(self.waiters.len() - 1)
+            } else {
+                x.saturating_sub(1)
+            }
+        });
+        self.table_state.select(Some(selected));
+    }
+
+    fn prompt_name(&mut self) {
+        let mut popup = InputPopup::new("Lock name", "/locks/job", self.shared_state.clone());
+        if let Some(ref name) = self.name {
+            popup = popup.with_value(name);
+        }
+        popup.show();
+        self.name_popup = Some(popup);
+    }
+
+    fn acquire(&mut self) {
+        let Some(name) = self.name.clone() else {
+            return;
+        };
+        if self.acquire_task.is_active() || self.own_waiter().is_some() {
+            self.show_message(
+                "Cannot acquire lock",
+                "Lock was already requested by this app",
+            );
+            return;
+        }
+        self.acquire_task
+            .start(|s| async move { s.acquire_lock(&name).await });
+        self.refreshed_at = None;
+    }
+
+    fn release(&mut self) {
+        if !self.acquire_task.is_active() && self.own_waiter().is_none() {
+            self.show_message("Cannot release lock", "Lock was not requested by this app");
+            return;
+        }
+        // etcd removes waiter, once its request is cancelled
+        self.acquire_task.abort();
+        if let Some(key) = self.own_waiter().map(|x| x.key.clone()) {
+            self.release_task
+                .start(|s| async move { s.release_lock(&key).await });
+        }
+        self.refreshed_at = None;
+    }
+
+    fn prompt_force_unlock(&mut self) {
+        let Some(holder) = self.waiters.first().cloned() else {
+            self.show_message("Cannot force-unlock", "Lock is not held");
+            return;
+        };
+        if holder.lease == 0 {
+            self.show_message("Cannot force-unlock", "Holder has no lease to revoke");
+            return;
+        }
+        if self.shared_state.is_session_lease(holder.lease) {
+            self.show_message(
+                "Cannot force-unlock",
+                "Lock is held by this app, release it instead",
+            );
+            return;
+        }
+        let mut popup = ConfirmationPopup::new(
+            format!("Revoke lease {:#x}?", holder.lease),
+            self.shared_state.clone(),
+        )
+        .with_explanation(format!(
+            "Lock is released by deleting {} along with all other keys attached to the lease, \
+             e.g. other locks of same client. Next waiter acquires the lock, while holder may \
+             still believe it holds it.",
+            holder.key
+        ));
+        popup.show();
+        self.force_unlock_confirmation_popup = Some(popup);
+        self.force_unlock_holder = Some(holder);
+    }
+
+    fn force_unlock(&mut self, lease: i64) {
+        self.force_unlock_task
+            .start(move |s| async move { s.revoke_lease(lease).await });
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    fn summary_lines(&self) -> Vec<Line<'static>> {
+        let Some(ref name) = self.name else {
+            return vec![Line::from("No lock name given, press (n) to enter it")];
+        };
+
+        let mut lines = vec![Line::from(format!("Lock: {name}"))];
+        lines.push(match self.waiters.first() {
+            Some(x) => Line::styled(
+                format!(
+                    "Held by {} (lease {:#x}, TTL {})",
+                    x.key,
+                    x.lease,
+                    format_ttl(x)
+                ),
+                Style::default().yellow(),
+            ),
+            None => Line::styled("Not held", Style::default().green()),
+        });
+        let testing = match self.own_waiter() {
+            Some(x) if self.waiters.first().is_some_and(|h| h.key == x.key) => {
+                Line::from("Acquired by this app for testing")
+            }
+            Some(_) => Line::from("This app waits for lock"),
+            None if self.acquire_task.is_active() => Line::from("This app requests lock"),
+            None => Line::default(),
+        };
+        lines.push(testing);
+        lines
+    }
+
+    fn waiter_rows(&self) -> Vec<Row<'static>> {
+        self.waiters
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                let state = if idx == 0 { "holder" } else { "waiting" };
+                let key = if self.shared_state.is_session_lease(x.lease) {
+                    format!("{} (this app)", x.key)
+                } else {
+                    x.key.clone()
+                };
+                let lease = if x.lease == 0 {
+                    "none".into()
+                } else {
+                    format!("{:#x}", x.lease)
+                };
+                let row = Row::new(vec![
+                    Cell::from((idx + 1).to_string()),
+                    Cell::from(state),
+                    Cell::from(key),
+                    Cell::from(x.create_revision.to_string()),
+                    Cell::from(lease),
+                    Cell::from(format_ttl(x)),
+                ]);
+                match (idx, x.ttl) {
+                    // waiter, whose lease expired, is about to be removed by etcd
+                    (_, None) if x.lease != 0 => row.style(Style::default().red()),
+                    (0, _) => row.style(Style::default().yellow()),
+                    _ => row,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Component for LockInspector {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.release_task.handle_key_event(event));
+            key_event!(self.force_unlock_task.handle_key_event(event));
+            if let Some(ref mut x) = self.name_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.force_unlock_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.move_selection(true);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.move_selection(false);
+                }
+                Input {
+                    key: Key::Char('n'),
+                    ..
+                } => {
+                    self.prompt_name();
+                }
+                Input {
+                    key: Key::Char('a'),
+                    ..
+                } => {
+                    self.acquire();
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } => {
+                    self.release();
+                }
+                Input {
+                    key: Key::Char('x') | Key::Delete,
+                    ..
+                } => {
+                    self.prompt_force_unlock();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.refresh();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::LocksDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(ref mut x) = self.name_popup {
+            if let Some(result) = x.status() {
+                self.name_popup = None;
+                if let Some(name) = result.into_done() {
+                    let name = name.trim_end_matches('/').to_string();
+                    self.set_name((!name.is_empty()).then_some(name));
+                }
+            }
+        }
+
+        if let Some(result) = self.acquire_task.try_ready() {
+            match result {
+                Ok(key) => self.show_message("Lock acquired", &format!("Lock is held as {key}")),
+                Err(err) => self.show_message("Cannot acquire lock", &err.to_string()),
+            }
+            self.refreshed_at = None;
+        }
+
+        if let Some(result) = self.release_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot release lock", &err.to_string());
+            }
+            self.refreshed_at = None;
+        }
+
+        if let Some(ref mut x) = self.force_unlock_confirmation_popup {
+            if let Some(result) = x.status() {
+                let holder = self.force_unlock_holder.take();
+                if let (true, Some(holder)) = (result.is_yes(), holder) {
+                    self.force_unlock(holder.lease);
+                }
+                self.force_unlock_confirmation_popup = None;
+            }
+        }
+
+        if let Some(result) = self.force_unlock_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot force-unlock", &err.to_string());
+            }
+            self.refreshed_at = None;
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        if let Some(result) = self.refresh_task.try_ready() {
+            self.refreshed_at = Some(Instant::now());
+            match result {
+                Ok(waiters) => {
+                    let selected_key = self.selected_waiter().map(|x| x.key.clone());
+                    self.waiters = waiters;
+                    self.error = None;
+                    let idx = selected_key
+                        .and_then(|key| self.waiters.iter().position(|x| x.key == key))
+                        .or((!self.waiters.is_empty()).then_some(0));
+                    self.table_state.select(idx);
+                }
+                Err(err) => self.error = Some(err.to_string()),
+            }
+        }
+
+        if self.is_visible()
+            && self
+                .refreshed_at
+                .is_none_or(|x| x.elapsed() >= REFRESH_INTERVAL)
+        {
+            self.refresh();
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Length(4), Constraint::Min(0)])
+                .split(rect);
+
+            let summary = Paragraph::new(self.summary_lines()).block(main_titled_block("Lock"));
+            frame.render_widget(summary, layout[0]);
+
+            let title = match self.error {
+                Some(ref x) => Line::styled(
+                    format!("Waiters (refresh failed: {x})"),
+                    Style::default().red(),
+                ),
+                None => Line::from(format!("Waiters ({})", self.waiters.len())),
+            };
+            let header = Row::new(vec!["#", "State", "Key", "Created", "Lease", "TTL"])
+                .style(Style::default().add_modifier(Modifier::BOLD));
+            let widths = [
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Min(20),
+                Constraint::Length(10),
+                Constraint::Length(17),
+                Constraint::Length(10),
+            ];
+            let table = Table::new(self.waiter_rows())
+                .header(header)
+                .widths(&widths)
+                .block(main_titled_block(title))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(table, layout[1], &mut self.table_state);
+
+            self.release_task.draw(frame, rect);
+            self.force_unlock_task.draw(frame, rect);
+            if let Some(ref mut x) = self.name_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.force_unlock_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.release_task.is_visible() {
+                return self.release_task.context_help();
+            }
+
+            if self.force_unlock_task.is_visible() {
+                return self.force_unlock_task.context_help();
+            }
+
+            if let Some(ref x) = self.name_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.force_unlock_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            let mut help = vec!["(Up/Down) scroll list".into(), "(n) lock name".into()];
+            if self.acquire_task.is_active() || self.own_waiter().is_some() {
+                help.push("(r) release lock".into());
+            } else if self.name.is_some() {
+                help.push("(a) acquire lock".into());
+            }
+            help.extend([
+                "(x/Del) force-unlock".into(),
+                "(R) refresh".into(),
+                "(Esc) return to key selection".into(),
+            ]);
+            help
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        if self.name.is_none() {
+            self.prompt_name();
+        }
+        self.refreshed_at = None;
+    }
+}
+
+fn format_ttl(waiter: &LockWaiter) -> String {
+    match (waiter.lease, waiter.ttl) {
+        (0, _) => "none".into(),
+        (_, Some(x)) => format!("{x}s"),
+        (_, None) => "expired".into(),
+    }
+}
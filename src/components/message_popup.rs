@@ -0,0 +1,104 @@
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Stylize,
+    widgets::{Borders, Clear, Paragraph, Wrap},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::SharedState,
+    ui::{calculate_center_rect, titled_block, Frame},
+};
+
+use super::Component;
+
+/// Popup with informational message, closed with Esc or Enter.
+pub struct MessagePopup {
+    title: String,
+    message: String,
+    is_closed: bool,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl MessagePopup {
+    pub fn new(title: impl ToString, message: impl ToString, shared_state: SharedState) -> Self {
+        Self {
+            title: title.to_string(),
+            message: message.to_string(),
+            is_closed: false,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+}
+
+impl Component for MessagePopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            if matches!(
+                event.into(),
+                Input {
+                    key: Key::Esc | Key::Enter,
+                    ..
+                }
+            ) {
+                self.is_closed = true;
+                self.shared_state.tick()?;
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        let block = titled_block(self.title.clone())
+            .borders(Borders::ALL)
+            .on_dark_gray();
+
+        let rect = calculate_center_rect(50, 8, frame.size());
+
+        let inner_layout = Layout::default()
+            .constraints(vec![Constraint::Min(0), Constraint::Max(1)])
+            .direction(Direction::Vertical)
+            .split(block.inner(rect));
+
+        let message_paragraph = Paragraph::new(self.message.clone())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        let close_paragraph = Paragraph::new("Esc/Enter").alignment(Alignment::Center);
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(block, rect);
+        frame.render_widget(message_paragraph, inner_layout[0]);
+        frame.render_widget(close_paragraph, inner_layout[1]);
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            vec!["(Esc/Enter) close".into()]
+        } else {
+            vec![]
+        }
+    }
+}
@@ -1,7 +1,13 @@
 pub use self::{
-    confirmation_popup::ConfirmationPopup, context_help::ContextHelp,
-    foreground_task::ForegroundTask, key_selector::KeySelector, new_key_popup::NewKeyPopup,
-    value_editor::ValueEditor,
+    alarm_indicator::AlarmIndicator, alarm_list::AlarmList, cluster_dashboard::ClusterDashboard,
+    confirmation_popup::ConfirmationPopup, conflict_popup::ConflictPopup,
+    context_help::ContextHelp, diff_popup::DiffPopup, foreground_task::ForegroundTask,
+    history_browser::HistoryBrowser, input_popup::InputPopup, key_selector::KeySelector,
+    keyspace_diff_popup::KeyspaceDiffPopup, lease_browser::LeaseBrowser,
+    lock_inspector::LockInspector, message_popup::MessagePopup, new_key_popup::NewKeyPopup,
+    permission_inspector::PermissionInspector, role_list::RoleList,
+    selection_popup::SelectionPopup, staging_review::StagingReview, txn_builder::TxnBuilder,
+    user_list::UserList, value_editor::ValueEditor, watch_panel::WatchPanel,
 };
 
 use anyhow::Result;
@@ -11,12 +17,30 @@ use ratatui::prelude::Rect;
 
 use crate::{events::KeyEventState, ui::Frame};
 
+mod alarm_indicator;
+mod alarm_list;
+mod cluster_dashboard;
 mod confirmation_popup;
+mod conflict_popup;
 mod context_help;
+mod diff_popup;
 mod foreground_task;
+mod history_browser;
+mod input_popup;
 mod key_selector;
+mod keyspace_diff_popup;
+mod lease_browser;
+mod lock_inspector;
+mod message_popup;
 mod new_key_popup;
+mod permission_inspector;
+mod role_list;
+mod selection_popup;
+mod staging_review;
+mod txn_builder;
+mod user_list;
 mod value_editor;
+mod watch_panel;
 
 #[allow(unused)]
 pub trait Component {
@@ -1,14 +1,16 @@
 use anyhow::Result;
 use crossterm::event::KeyEvent;
 use ratatui::{
-    style::{Style, Stylize},
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
     widgets::{Borders, Clear},
 };
 use tui_textarea::{Input, Key, TextArea};
 
 use crate::{
     events::KeyEventState,
-    shared_state::SharedState,
+    shared_state::{PutLease, SharedState},
     ui::{calculate_center_rect, titled_block, Frame},
 };
 
@@ -19,13 +21,13 @@ use super::Component;
 #[derive(Clone, Debug)]
 pub enum NewKeyResult {
     Cancel,
-    Done(String),
+    Done { key: String, lease: PutLease },
 }
 
 impl NewKeyResult {
-    pub fn as_done(self) -> Option<String> {
-        if let Self::Done(v) = self {
-            Some(v)
+    pub fn into_done(self) -> Option<(String, PutLease)> {
+        if let Self::Done { key, lease } = self {
+            Some((key, lease))
         } else {
             None
         }
@@ -34,10 +36,14 @@ impl NewKeyResult {
 
 pub struct NewKeyPopup {
     textarea: TextArea<'static>,
+    lease_textarea: TextArea<'static>,
+    is_lease_focused: bool,
+    lease_error: Option<String>,
     result: Option<NewKeyResult>,
 
     is_visible: bool,
 
+    #[allow(unused)]
     shared_state: SharedState,
 }
 
@@ -47,8 +53,16 @@ impl NewKeyPopup {
         textarea.set_cursor_line_style(Style::default());
         textarea.set_placeholder_text("Enter new key name");
 
+        let mut lease_textarea = TextArea::default();
+        lease_textarea.set_cursor_line_style(Style::default());
+        lease_textarea.set_cursor_style(Style::default());
+        lease_textarea.set_placeholder_text("TTL (e.g. 30s) or lease ID (e.g. 0x694d...)");
+
         Self {
             textarea,
+            lease_textarea,
+            is_lease_focused: false,
+            lease_error: None,
             result: None,
 
             is_visible: false,
@@ -58,13 +72,34 @@ impl NewKeyPopup {
     }
 
     fn set_done(&mut self) {
-        if let Some(x) = self.textarea.lines().get(0).cloned() {
-            if !x.is_empty() {
-                self.result = Some(NewKeyResult::Done(x))
-            }
+        let Some(key) = self.textarea.lines().first().cloned() else {
+            return;
+        };
+        if key.is_empty() {
+            return;
+        }
+
+        let lease = match self.lease_textarea.lines().first() {
+            Some(x) if !x.trim().is_empty() => x.parse(),
+            _ => Ok(PutLease::None),
+        };
+        match lease {
+            Ok(lease) => self.result = Some(NewKeyResult::Done { key, lease }),
+            Err(err) => self.lease_error = Some(err.to_string()),
         }
     }
 
+    fn toggle_focus(&mut self) {
+        self.is_lease_focused = !self.is_lease_focused;
+        let (focused, unfocused) = if self.is_lease_focused {
+            (&mut self.lease_textarea, &mut self.textarea)
+        } else {
+            (&mut self.textarea, &mut self.lease_textarea)
+        };
+        focused.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED));
+        unfocused.set_cursor_style(Style::default());
+    }
+
     pub fn status(&self) -> Option<NewKeyResult> {
         self.result.clone()
     }
@@ -82,6 +117,13 @@ impl Component for NewKeyPopup {
                 } => {
                     self.set_done();
                 }
+                Input { key: Key::Tab, .. } => {
+                    self.toggle_focus();
+                }
+                input if self.is_lease_focused => {
+                    self.lease_error = None;
+                    self.lease_textarea.input(input);
+                }
                 input => {
                     self.textarea.input(input);
                 }
@@ -96,10 +138,24 @@ impl Component for NewKeyPopup {
         let block = titled_block("New key").borders(Borders::ALL).on_dark_gray();
         self.textarea.set_block(block);
 
-        let rect = calculate_center_rect(30, 3, frame.size());
+        let lease_title = match self.lease_error {
+            Some(ref x) => Line::styled(x.clone(), Style::default().red()),
+            None => Line::from("Lease (optional)"),
+        };
+        let lease_block = titled_block(lease_title)
+            .borders(Borders::ALL)
+            .on_dark_gray();
+        self.lease_textarea.set_block(lease_block);
+
+        let rect = calculate_center_rect(40, 6, frame.size());
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(3), Constraint::Length(3)])
+            .split(rect);
 
         frame.render_widget(Clear, rect);
-        frame.render_widget(self.textarea.widget(), rect);
+        frame.render_widget(self.textarea.widget(), layout[0]);
+        frame.render_widget(self.lease_textarea.widget(), layout[1]);
     }
 
     fn set_visibility(&mut self, value: bool) {
@@ -112,7 +168,11 @@ impl Component for NewKeyPopup {
 
     fn context_help(&self) -> Vec<String> {
         if self.is_visible() {
-            vec!["(Enter) done".into(), "(Esc) cancel".into()]
+            vec![
+                "(Enter) done".into(),
+                "(Tab) switch between key and lease".into(),
+                "(Esc) cancel".into(),
+            ]
         } else {
             vec![]
         }
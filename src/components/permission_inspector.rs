@@ -0,0 +1,288 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph, Wrap},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    permission::{Access, EffectivePermissions, KeyRange},
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ForegroundTask, InputPopup, MessagePopup};
+
+/// Evaluation of operations, which current user is allowed on key or prefix.
+pub struct PermissionInspector {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    /// Key or prefix, which access is evaluated for.
+    target: KeyRange,
+    permissions: Option<EffectivePermissions>,
+    list_state: ListState,
+
+    load_permissions_task: ForegroundTask<Result<EffectivePermissions>>,
+    target_popup: Option<InputPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl PermissionInspector {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            target: KeyRange::All,
+            permissions: None,
+            list_state: ListState::default(),
+
+            load_permissions_task: ForegroundTask::new("Loading permissions", shared_state),
+            target_popup: None,
+            message_popup: None,
+        }
+    }
+
+    /// Show access to key selected in key selection, or to all keys if none was selected.
+    pub fn open(&mut self, selected_key: Option<String>) {
+        self.target = selected_key.map_or(KeyRange::All, |x| KeyRange::Key(x.into_bytes()));
+        self.show();
+    }
+
+    fn reload_permissions(&mut self) {
+        self.load_permissions_task
+            .start(|s| async move { s.load_effective_permissions().await });
+    }
+
+    fn permissions_len(&self) -> usize {
+        self.permissions
+            .as_ref()
+            .map_or(0, |x| x.permissions().count())
+    }
+
+    fn prompt_target(&mut self) {
+        let mut popup = InputPopup::new("Key or prefix", "prefix /app/", self.shared_state.clone())
+            .with_value(&self.target.to_string());
+        popup.show();
+        self.target_popup = Some(popup);
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    fn draw_summary(&self, frame: &mut Frame, rect: Rect) {
+        let access_span = |access| match access {
+            Access::Allowed => Span::raw("allowed").green(),
+            Access::Partial => Span::raw("allowed for some keys").yellow(),
+            Access::Denied => Span::raw("denied").red(),
+        };
+
+        let mut lines = vec![Line::from(format!("Target: {}", self.target))];
+        if let Some(ref permissions) = self.permissions {
+            let user = permissions.user.as_deref().unwrap_or("(none)");
+            lines.insert(0, Line::from(format!("User: {user}")));
+            if let Some(ref reason) = permissions.unrestricted {
+                lines.push(Line::from(format!("All operations are allowed: {reason}")));
+            } else {
+                let roles = permissions
+                    .roles
+                    .iter()
+                    .map(|x| x.name.as_str())
+                    .collect::<Vec<_>>();
+                lines.insert(1, Line::from(format!("Roles: {}", roles.join(", "))));
+                lines.extend([
+                    Line::from(vec![
+                        Span::raw("Read: "),
+                        access_span(permissions.read_access(&self.target)),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("Write: "),
+                        access_span(permissions.write_access(&self.target)),
+                    ]),
+                ]);
+            }
+        }
+
+        let widget = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(main_titled_block("Effective permissions"));
+        frame.render_widget(widget, rect);
+    }
+
+    fn draw_permissions(&mut self, frame: &mut Frame, rect: Rect) {
+        let items = self.permissions.as_ref().map_or(vec![], |permissions| {
+            permissions
+                .permissions()
+                .map(|(role, x)| {
+                    let text = format!("{role:<16} {:<10} {}", x.kind.to_string(), x.range);
+                    let style = if x.range.covers(&self.target) {
+                        Style::default().green()
+                    } else if x.range.overlaps(&self.target) {
+                        Style::default().yellow()
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Line::styled(text, style))
+                })
+                .collect()
+        });
+        let widget = List::new(items)
+            .block(main_titled_block(
+                "Permissions by role (matching target highlighted)",
+            ))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(widget, rect, &mut self.list_state);
+    }
+}
+
+impl Component for PermissionInspector {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_permissions_task.handle_key_event(event));
+            if let Some(ref mut x) = self.target_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(
+                            x.saturating_add(1),
+                            self.permissions_len().saturating_sub(1),
+                        )
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Char('k'),
+                    ..
+                } => {
+                    self.prompt_target();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_permissions();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::PermissionsDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_permissions_task.try_ready() {
+            match result {
+                Ok(permissions) => {
+                    self.permissions = Some(permissions);
+                    self.list_state
+                        .select((self.permissions_len() > 0).then_some(0));
+                }
+                Err(err) => self.show_message("Cannot load permissions", &err.to_string()),
+            }
+        }
+
+        if let Some(ref mut x) = self.target_popup {
+            if let Some(result) = x.status() {
+                self.target_popup = None;
+                if let Some(input) = result.into_done() {
+                    match input.parse() {
+                        Ok(target) => self.target = target,
+                        Err(err) => self.show_message("Invalid key or prefix", &err.to_string()),
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Length(6), Constraint::Min(0)])
+                .split(rect);
+
+            self.draw_summary(frame, layout[0]);
+            self.draw_permissions(frame, layout[1]);
+
+            self.load_permissions_task.draw(frame, rect);
+            if let Some(ref mut x) = self.target_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_permissions_task.is_visible() {
+                return self.load_permissions_task.context_help();
+            }
+
+            if let Some(ref x) = self.target_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(k) change key or prefix".into(),
+                "(R) reload".into(),
+                "(Esc) return to key selection".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.reload_permissions();
+    }
+}
@@ -0,0 +1,601 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    permission::{Permission, PermissionKind},
+    shared_state::Role,
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, InputPopup, MessagePopup};
+
+const PERMISSION_PLACEHOLDER: &str = "readwrite prefix /app/";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Pane {
+    Roles,
+    Permissions,
+}
+
+/// Roles of etcd auth along with their permissions.
+pub struct RoleList {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    roles: Vec<Role>,
+    list_state: ListState,
+    permissions_list_state: ListState,
+    focused_pane: Pane,
+    /// Key selected in key selection, which new permissions are prefilled with.
+    selected_key: Option<String>,
+
+    load_roles_task: ForegroundTask<Result<Vec<Role>>>,
+    update_task: ForegroundTask<Result<()>>,
+
+    name_popup: Option<InputPopup>,
+    grant_popup: Option<InputPopup>,
+    /// Permission being edited, which is replaced with input of `edit_popup`.
+    edited_permission: Option<Permission>,
+    edit_popup: Option<InputPopup>,
+    delete_confirmation_popup: Option<ConfirmationPopup>,
+    revoke_confirmation_popup: Option<ConfirmationPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl RoleList {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            roles: vec![],
+            list_state: ListState::default(),
+            permissions_list_state: ListState::default(),
+            focused_pane: Pane::Roles,
+            selected_key: None,
+
+            load_roles_task: ForegroundTask::new("Loading roles", shared_state.clone()),
+            update_task: ForegroundTask::new("Updating role", shared_state),
+
+            name_popup: None,
+            grant_popup: None,
+            edited_permission: None,
+            edit_popup: None,
+            delete_confirmation_popup: None,
+            revoke_confirmation_popup: None,
+            message_popup: None,
+        }
+    }
+
+    /// Show roles, with key selected in key selection, if any.
+    pub fn open(&mut self, selected_key: Option<String>) {
+        self.selected_key = selected_key;
+        self.show();
+    }
+
+    fn selected_role(&self) -> Option<&Role> {
+        self.list_state.selected().and_then(|x| self.roles.get(x))
+    }
+
+    fn selected_permission(&self) -> Option<&Permission> {
+        let role = self.selected_role()?;
+        self.permissions_list_state
+            .selected()
+            .and_then(|x| role.permissions.get(x))
+    }
+
+    fn reload_roles(&mut self) {
+        self.load_roles_task
+            .start(|s| async move { s.load_roles().await });
+    }
+
+    fn select_role(&mut self, idx: Option<usize>) {
+        self.list_state.select(idx);
+        let has_permissions = self
+            .selected_role()
+            .is_some_and(|x| !x.permissions.is_empty());
+        self.permissions_list_state
+            .select(has_permissions.then_some(0));
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let (list_state, len) = match self.focused_pane {
+            Pane::Roles => (&self.list_state, self.roles.len()),
+            Pane::Permissions => (
+                &self.permissions_list_state,
+                self.selected_role().map_or(0, |x| x.permissions.len()),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        let selected = list_state.selected().map_or(0, |x| {
+            if down {
+                min(x.saturating_add(1), len - 1)
+            } else {
+                x.saturating_sub(1)
+            }
+        });
+        match self.focused_pane {
+            Pane::Roles => self.select_role(Some(selected)),
+            Pane::Permissions => self.permissions_list_state.select(Some(selected)),
+        }
+    }
+
+    fn toggle_focused_pane(&mut self) {
+        self.focused_pane = match self.focused_pane {
+            Pane::Roles => Pane::Permissions,
+            Pane::Permissions => Pane::Roles,
+        };
+    }
+
+    fn prompt_name(&mut self) {
+        let mut popup = InputPopup::new("New role", "name", self.shared_state.clone());
+        popup.show();
+        self.name_popup = Some(popup);
+    }
+
+    fn add_role(&mut self, name: String) {
+        self.update_task
+            .set_description(format!("Adding role '{name}'"));
+        self.update_task
+            .start(|s| async move { s.add_role(&name).await });
+    }
+
+    fn prompt_delete(&mut self) {
+        if let Some(role) = self.selected_role() {
+            let mut popup = ConfirmationPopup::new(
+                format!("Delete role '{}'?", role.name),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.delete_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn delete_selected_role(&mut self) {
+        if let Some(role) = self.selected_role().map(|x| x.name.clone()) {
+            self.update_task
+                .set_description(format!("Deleting role '{role}'"));
+            self.update_task
+                .start(|s| async move { s.delete_role(&role).await });
+        }
+    }
+
+    /// Prompt for new permission, prefilled with key selected in key selection, which is
+    /// granted as prefix if it looks like a directory.
+    fn prompt_grant(&mut self) {
+        let Some(role) = self.selected_role() else {
+            return;
+        };
+        let mut popup = InputPopup::new(
+            format!("Grant permission to '{}'", role.name),
+            PERMISSION_PLACEHOLDER,
+            self.shared_state.clone(),
+        );
+        if let Some(ref key) = self.selected_key {
+            let range = if key.ends_with('/') { "prefix" } else { "key" };
+            popup = popup.with_value(&format!("readwrite {range} {key}"));
+        }
+        popup.show();
+        self.grant_popup = Some(popup);
+    }
+
+    fn grant_permission(&mut self, input: &str) {
+        let Some(role) = self.selected_role().map(|x| x.name.clone()) else {
+            return;
+        };
+        match input.parse::<Permission>() {
+            Ok(permission) => {
+                self.update_task
+                    .set_description(format!("Granting {permission}"));
+                self.update_task
+                    .start(|s| async move { s.grant_permission(&role, &permission).await });
+            }
+            Err(err) => self.show_message("Invalid permission", &err.to_string()),
+        }
+    }
+
+    fn prompt_edit(&mut self) {
+        let (Some(role), Some(permission)) = (self.selected_role(), self.selected_permission())
+        else {
+            return;
+        };
+        if !permission.range.is_utf8() {
+            self.show_message(
+                "Cannot edit permission",
+                "Keys of permission aren't valid UTF-8, so they cannot be edited as text. Revoke \
+                 permission and grant new one instead.",
+            );
+            return;
+        }
+        let mut popup = InputPopup::new(
+            format!("Edit permission of '{}'", role.name),
+            PERMISSION_PLACEHOLDER,
+            self.shared_state.clone(),
+        )
+        .with_value(&permission.to_string());
+        popup.show();
+        self.edited_permission = Some(permission.clone());
+        self.edit_popup = Some(popup);
+    }
+
+    fn replace_permission(&mut self, old: Permission, input: &str) {
+        let Some(role) = self.selected_role().map(|x| x.name.clone()) else {
+            return;
+        };
+        match input.parse::<Permission>() {
+            Ok(new) if new == old => {}
+            Ok(new) => {
+                self.update_task
+                    .set_description(format!("Replacing {old} with {new}"));
+                self.update_task
+                    .start(|s| async move { s.replace_permission(&role, &old, &new).await });
+            }
+            Err(err) => self.show_message("Invalid permission", &err.to_string()),
+        }
+    }
+
+    fn prompt_revoke(&mut self) {
+        if let (Some(role), Some(permission)) = (self.selected_role(), self.selected_permission()) {
+            let mut popup = ConfirmationPopup::new(
+                format!("Revoke {permission} from '{}'?", role.name),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.revoke_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn revoke_selected_permission(&mut self) {
+        let role = self.selected_role().map(|x| x.name.clone());
+        let permission = self.selected_permission().cloned();
+        if let (Some(role), Some(permission)) = (role, permission) {
+            self.update_task
+                .set_description(format!("Revoking {permission}"));
+            self.update_task
+                .start(|s| async move { s.revoke_permission(&role, &permission).await });
+        }
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+}
+
+impl Component for RoleList {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_roles_task.handle_key_event(event));
+            key_event!(self.update_task.handle_key_event(event));
+            if let Some(ref mut x) = self.name_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.grant_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.edit_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.delete_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.move_selection(true);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.move_selection(false);
+                }
+                Input {
+                    key: Key::Tab | Key::Left | Key::Right,
+                    ..
+                } => {
+                    self.toggle_focused_pane();
+                }
+                Input {
+                    key: Key::Char('a'),
+                    ..
+                } => {
+                    self.prompt_name();
+                }
+                Input {
+                    key: Key::Char('g'),
+                    ..
+                } => {
+                    self.prompt_grant();
+                }
+                Input {
+                    key: Key::Char('e') | Key::Enter,
+                    ..
+                } if self.focused_pane == Pane::Permissions => {
+                    self.prompt_edit();
+                }
+                Input {
+                    key: Key::Char('r') | Key::Char('d') | Key::Delete,
+                    ..
+                } if self.focused_pane == Pane::Permissions => {
+                    self.prompt_revoke();
+                }
+                Input {
+                    key: Key::Char('d') | Key::Delete,
+                    ..
+                } => {
+                    self.prompt_delete();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_roles();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::RolesDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_roles_task.try_ready() {
+            match result {
+                Ok(roles) => {
+                    let selected_name = self.selected_role().map(|x| x.name.clone());
+                    let selected_permission = self.permissions_list_state.selected();
+                    self.roles = roles;
+                    let idx = selected_name
+                        .as_ref()
+                        .and_then(|name| self.roles.iter().position(|x| x.name == *name))
+                        .or((!self.roles.is_empty()).then_some(0));
+                    self.select_role(idx);
+                    // keep position in permissions of same role, e.g. after permission was edited
+                    let permissions_len = match self.selected_role() {
+                        Some(x) if Some(&x.name) == selected_name.as_ref() => x.permissions.len(),
+                        _ => 0,
+                    };
+                    if let (Some(x), true) = (selected_permission, permissions_len > 0) {
+                        self.permissions_list_state
+                            .select(Some(min(x, permissions_len - 1)));
+                    }
+                }
+                Err(err) => self.show_message("Cannot load roles", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.update_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot update role", &err.to_string());
+            }
+            self.reload_roles();
+        }
+
+        if let Some(ref mut x) = self.name_popup {
+            if let Some(result) = x.status() {
+                if let Some(name) = result.into_done() {
+                    self.add_role(name);
+                }
+                self.name_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.grant_popup {
+            if let Some(result) = x.status() {
+                self.grant_popup = None;
+                if let Some(input) = result.into_done() {
+                    self.grant_permission(&input);
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.edit_popup {
+            if let Some(result) = x.status() {
+                self.edit_popup = None;
+                let old = self.edited_permission.take();
+                if let (Some(input), Some(old)) = (result.into_done(), old) {
+                    self.replace_permission(old, &input);
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.delete_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.delete_selected_role();
+                }
+                self.delete_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.revoke_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.revoke_selected_permission();
+                }
+                self.revoke_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(40), Constraint::Min(0)])
+                .split(rect);
+
+            let highlight_style = |pane| {
+                if self.focused_pane == pane {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                }
+            };
+
+            let items = self
+                .roles
+                .iter()
+                .map(|x| {
+                    ListItem::new(format!(
+                        "{} ({} permission(s))",
+                        x.name,
+                        x.permissions.len()
+                    ))
+                })
+                .collect::<Vec<_>>();
+            let list_widget = List::new(items)
+                .block(main_titled_block("Roles"))
+                .highlight_style(highlight_style(Pane::Roles));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (permissions_title, permissions) = match self.selected_role() {
+                Some(x) => (
+                    format!("Permissions of '{}'", x.name),
+                    x.permissions
+                        .iter()
+                        .map(|x| {
+                            let kind = Span::raw(format!("{:<10}", x.kind.to_string()));
+                            let kind = match x.kind {
+                                PermissionKind::Read => kind.green(),
+                                PermissionKind::Write => kind.yellow(),
+                                PermissionKind::ReadWrite => kind.cyan(),
+                            };
+                            ListItem::new(Line::from(vec![kind, Span::raw(x.range.to_string())]))
+                        })
+                        .collect(),
+                ),
+                None => ("Permissions".into(), vec![]),
+            };
+            let permissions_widget = List::new(permissions)
+                .block(main_titled_block(permissions_title))
+                .highlight_style(highlight_style(Pane::Permissions));
+            frame.render_stateful_widget(
+                permissions_widget,
+                layout[1],
+                &mut self.permissions_list_state,
+            );
+
+            self.load_roles_task.draw(frame, rect);
+            self.update_task.draw(frame, rect);
+            if let Some(ref mut x) = self.name_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.grant_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.edit_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.delete_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_roles_task.is_visible() {
+                return self.load_roles_task.context_help();
+            }
+
+            if self.update_task.is_visible() {
+                return self.update_task.context_help();
+            }
+
+            if let Some(ref x) = self.name_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.grant_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.edit_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.delete_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.revoke_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            let mut help = vec![
+                "(Up/Down) scroll list".into(),
+                "(Tab) switch between roles and permissions".into(),
+                "(a) add role".into(),
+                "(g) grant permission".into(),
+            ];
+            match self.focused_pane {
+                Pane::Roles => help.push("(d/Del) delete role".into()),
+                Pane::Permissions => help.extend([
+                    "(e/Enter) edit permission".into(),
+                    "(r/Del) revoke permission".into(),
+                ]),
+            }
+            help.extend(["(R) reload".into(), "(Esc) return to key selection".into()]);
+            help
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.focused_pane = Pane::Roles;
+        self.reload_roles();
+    }
+}
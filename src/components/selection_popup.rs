@@ -0,0 +1,149 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::Rect,
+    style::{Modifier, Style, Stylize},
+    widgets::{Borders, Clear, List, ListItem, ListState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::KeyEventState,
+    shared_state::SharedState,
+    ui::{calculate_center_rect, titled_block, Frame},
+};
+
+use super::Component;
+
+#[derive(Copy, Clone, Debug)]
+pub enum SelectionResult {
+    Cancel,
+    /// Index of selected item.
+    Done(usize),
+}
+
+impl SelectionResult {
+    pub fn into_done(self) -> Option<usize> {
+        if let Self::Done(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+}
+
+/// Popup for selecting one of items in list.
+pub struct SelectionPopup {
+    title: String,
+    items: Vec<String>,
+    list_state: ListState,
+    result: Option<SelectionResult>,
+
+    is_visible: bool,
+
+    shared_state: SharedState,
+}
+
+impl SelectionPopup {
+    pub fn new(title: impl ToString, items: Vec<String>, shared_state: SharedState) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select((!items.is_empty()).then_some(0));
+
+        Self {
+            title: title.to_string(),
+            items,
+            list_state,
+            result: None,
+
+            is_visible: false,
+
+            shared_state,
+        }
+    }
+
+    fn set_result(&mut self, result: SelectionResult) -> Result<()> {
+        self.result = Some(result);
+        self.shared_state.tick()?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> Option<SelectionResult> {
+        self.result
+    }
+}
+
+impl Component for SelectionPopup {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(x.saturating_add(1), self.items.len().saturating_sub(1))
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    if let Some(x) = selected_id {
+                        self.set_result(SelectionResult::Done(x))?;
+                    }
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.set_result(SelectionResult::Cancel)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _: Rect) {
+        let block = titled_block(self.title.clone())
+            .borders(Borders::ALL)
+            .on_dark_gray();
+
+        let height = min(self.items.len(), 10) as u16 + 2;
+        let rect = calculate_center_rect(50, height, frame.size());
+
+        let items = self
+            .items
+            .iter()
+            .map(|x| ListItem::new(x.clone()))
+            .collect::<Vec<_>>();
+        let list_widget = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_widget(Clear, rect);
+        frame.render_stateful_widget(list_widget, rect, &mut self.list_state);
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(Enter) select".into(),
+                "(Esc) cancel".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::{CommitResult, StagedChange},
+    ui::{main_titled_block, unified_diff_lines, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, MessagePopup};
+
+/// Review of staged changes before committing or discarding them.
+pub struct StagingReview {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    changes: Vec<StagedChange>,
+    list_state: ListState,
+
+    commit_confirmation_popup: Option<ConfirmationPopup>,
+    discard_confirmation_popup: Option<ConfirmationPopup>,
+    commit_task: ForegroundTask<Result<CommitResult>>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl StagingReview {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            changes: vec![],
+            list_state: ListState::default(),
+
+            commit_confirmation_popup: None,
+            discard_confirmation_popup: None,
+            commit_task: ForegroundTask::new("Committing staged changes", shared_state),
+            message_popup: None,
+        }
+    }
+
+    fn reload_changes(&mut self) {
+        self.changes = self.shared_state.staged_changes();
+        let selected = self
+            .list_state
+            .selected()
+            .map_or(0, |x| min(x, self.changes.len().saturating_sub(1)));
+        self.list_state
+            .select((!self.changes.is_empty()).then_some(selected));
+    }
+
+    fn unstage_selected(&mut self) {
+        if let Some(idx) = self.list_state.selected() {
+            self.shared_state.unstage(idx);
+            self.reload_changes();
+        }
+    }
+
+    fn prompt_commit(&mut self) {
+        if self.changes.is_empty() {
+            return;
+        }
+        let mut popup = ConfirmationPopup::new(
+            format!(
+                "Commit {} staged change(s) in single transaction?",
+                self.changes.len()
+            ),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.commit_confirmation_popup = Some(popup);
+    }
+
+    fn prompt_discard(&mut self) {
+        if self.changes.is_empty() {
+            return;
+        }
+        let mut popup = ConfirmationPopup::new(
+            format!("Discard {} staged change(s)?", self.changes.len()),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.discard_confirmation_popup = Some(popup);
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    /// Diff of all changes along with index of first line of each one.
+    fn diff_lines(&self) -> (Vec<Line<'static>>, Vec<usize>) {
+        let mut lines = vec![];
+        let mut offsets = vec![];
+        for change in &self.changes {
+            offsets.push(lines.len());
+            let (text, style) = change_summary(change);
+            lines.push(Line::styled(text, style.add_modifier(Modifier::BOLD)));
+            match change {
+                StagedChange::Put {
+                    value, old_value, ..
+                } => lines.extend(unified_diff_lines(
+                    old_value.as_deref().unwrap_or_default(),
+                    value,
+                )),
+                StagedChange::Delete { old_value, .. } => {
+                    lines.extend(unified_diff_lines(old_value, ""));
+                }
+                // rename is committed as deletion of old key and put of new one
+                StagedChange::Rename {
+                    from, to, value, ..
+                } => {
+                    lines.push(Line::styled(format!("- {from}"), Style::default().red()));
+                    lines.extend(unified_diff_lines(value, ""));
+                    lines.push(Line::styled(format!("+ {to}"), Style::default().green()));
+                    lines.extend(unified_diff_lines("", value));
+                }
+            }
+            lines.push(Line::default());
+        }
+        (lines, offsets)
+    }
+}
+
+impl Component for StagingReview {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            if let Some(ref mut x) = self.commit_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.discard_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.commit_task.handle_key_event(event));
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.list_state.select(Some(selected_id.map_or(0, |x| {
+                        min(x.saturating_add(1), self.changes.len().saturating_sub(1))
+                    })));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input {
+                    key: Key::Char('u') | Key::Delete,
+                    ..
+                } => {
+                    self.unstage_selected();
+                }
+                Input {
+                    key: Key::Char('c'),
+                    ..
+                } => {
+                    self.prompt_commit();
+                }
+                Input {
+                    key: Key::Char('x'),
+                    ..
+                } => {
+                    self.prompt_discard();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::StagingReviewDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(ref mut x) = self.commit_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.commit_task
+                        .start(|s| async move { s.commit_staged().await });
+                }
+                self.commit_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.discard_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.shared_state.discard_staged();
+                    self.reload_changes();
+                }
+                self.discard_confirmation_popup = None;
+            }
+        }
+
+        if let Some(result) = self.commit_task.try_ready() {
+            match result {
+                Ok(CommitResult::Done { revision }) => {
+                    let message = format!(
+                        "Committed {} change(s) at revision {revision}",
+                        self.changes.len()
+                    );
+                    self.show_message("Committed", &message);
+                }
+                Ok(CommitResult::Conflict(keys)) => {
+                    let message = format!(
+                        "Keys modified since changes were staged: {}. Nothing was committed.",
+                        keys.join(", ")
+                    );
+                    self.show_message("Conflict", &message);
+                }
+                Err(err) => self.show_message("Cannot commit", &err.to_string()),
+            }
+            self.reload_changes();
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(35), Constraint::Min(0)])
+                .split(rect);
+
+            let items = self
+                .changes
+                .iter()
+                .map(|x| {
+                    let (text, style) = change_summary(x);
+                    ListItem::new(Line::styled(text, style))
+                })
+                .collect::<Vec<_>>();
+            let list_widget = List::new(items)
+                .block(main_titled_block(format!(
+                    "Staged changes ({})",
+                    self.changes.len()
+                )))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (lines, offsets) = self.diff_lines();
+            let scroll = self
+                .list_state
+                .selected()
+                .and_then(|x| offsets.get(x))
+                .copied()
+                .unwrap_or_default();
+            let diff_widget = Paragraph::new(lines)
+                .block(main_titled_block("Diff"))
+                .scroll((scroll as u16, 0));
+            frame.render_widget(diff_widget, layout[1]);
+
+            if let Some(ref mut x) = self.commit_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.discard_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            self.commit_task.draw(frame, rect);
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if let Some(ref x) = self.commit_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.discard_confirmation_popup {
+                return x.context_help();
+            }
+
+            if self.commit_task.is_visible() {
+                return self.commit_task.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(u/Del) unstage change".into(),
+                "(c) commit all".into(),
+                "(x) discard all".into(),
+                "(Esc) return to key selection".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.reload_changes();
+    }
+}
+
+fn change_summary(change: &StagedChange) -> (String, Style) {
+    match change {
+        StagedChange::Put {
+            key,
+            mod_revision: 0,
+            ..
+        } => (format!("+ {key}"), Style::default().green()),
+        StagedChange::Put { key, .. } => (format!("~ {key}"), Style::default().yellow()),
+        StagedChange::Delete { key, .. } => (format!("- {key}"), Style::default().red()),
+        StagedChange::Rename { from, to, .. } => {
+            (format!("R {from} -> {to}"), Style::default().cyan())
+        }
+    }
+}
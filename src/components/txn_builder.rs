@@ -0,0 +1,459 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    txn::{Transaction, TxnOpOutcome, TxnOperation, TxnOutcome},
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{Component, ConfirmationPopup, ForegroundTask, InputPopup, MessagePopup};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Section {
+    Compares,
+    Success,
+    Failure,
+}
+
+impl Section {
+    fn next(self) -> Self {
+        match self {
+            Self::Compares => Self::Success,
+            Self::Success => Self::Failure,
+            Self::Failure => Self::Compares,
+        }
+    }
+}
+
+/// Editor of compare/success/failure transactions.
+pub struct TxnBuilder {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    transaction: Transaction,
+    focused_section: Section,
+    compares_list_state: ListState,
+    success_list_state: ListState,
+    failure_list_state: ListState,
+    /// Last executed transaction along with its outcome.
+    executed: Option<(Transaction, TxnOutcome)>,
+
+    /// Popup for new or edited item, along with index of edited one.
+    item_popup: Option<(Option<usize>, InputPopup)>,
+    execute_confirmation_popup: Option<ConfirmationPopup>,
+    execute_task: ForegroundTask<Result<TxnOutcome>>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl TxnBuilder {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            transaction: Transaction::default(),
+            focused_section: Section::Compares,
+            compares_list_state: ListState::default(),
+            success_list_state: ListState::default(),
+            failure_list_state: ListState::default(),
+            executed: None,
+
+            item_popup: None,
+            execute_confirmation_popup: None,
+            execute_task: ForegroundTask::new("Executing transaction", shared_state),
+            message_popup: None,
+        }
+    }
+
+    fn section_len(&self, section: Section) -> usize {
+        match section {
+            Section::Compares => self.transaction.compares.len(),
+            Section::Success => self.transaction.success.len(),
+            Section::Failure => self.transaction.failure.len(),
+        }
+    }
+
+    fn list_state_mut(&mut self, section: Section) -> &mut ListState {
+        match section {
+            Section::Compares => &mut self.compares_list_state,
+            Section::Success => &mut self.success_list_state,
+            Section::Failure => &mut self.failure_list_state,
+        }
+    }
+
+    fn operations_mut(&mut self, section: Section) -> Option<&mut Vec<TxnOperation>> {
+        match section {
+            Section::Compares => None,
+            Section::Success => Some(&mut self.transaction.success),
+            Section::Failure => Some(&mut self.transaction.failure),
+        }
+    }
+
+    fn selected_item(&mut self) -> Option<usize> {
+        let section = self.focused_section;
+        let len = self.section_len(section);
+        self.list_state_mut(section).selected().filter(|x| *x < len)
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let section = self.focused_section;
+        let len = self.section_len(section);
+        if len == 0 {
+            return;
+        }
+        let list_state = self.list_state_mut(section);
+        let selected = list_state.selected().map_or(0, |x| {
+            if down {
+                min(x.saturating_add(1), len - 1)
+            } else {
+                x.saturating_sub(1)
+            }
+        });
+        list_state.select(Some(selected));
+    }
+
+    fn prompt_item(&mut self, edited: Option<usize>) {
+        let (title, placeholder) = match self.focused_section {
+            Section::Compares => ("Compare", "e.g. mod(\"key\") > \"5\""),
+            Section::Success => ("Success operation", "put key value, get key or del key"),
+            Section::Failure => ("Failure operation", "put key value, get key or del key"),
+        };
+        let value = edited.map(|idx| match self.focused_section {
+            Section::Compares => self.transaction.compares[idx].to_string(),
+            Section::Success => self.transaction.success[idx].to_string(),
+            Section::Failure => self.transaction.failure[idx].to_string(),
+        });
+
+        let mut popup = InputPopup::new(title, placeholder, self.shared_state.clone())
+            .with_value(value.as_deref().unwrap_or_default());
+        popup.show();
+        self.item_popup = Some((edited, popup));
+    }
+
+    fn save_item(&mut self, edited: Option<usize>, text: &str) {
+        let section = self.focused_section;
+        let result = match self.operations_mut(section) {
+            None => text
+                .parse()
+                .map(|x| upsert(&mut self.transaction.compares, edited, x)),
+            Some(operations) => text.parse().map(|x| upsert(operations, edited, x)),
+        };
+        match result {
+            Ok(idx) => self.list_state_mut(section).select(Some(idx)),
+            Err(err) => self.show_message("Invalid input", &err.to_string()),
+        }
+    }
+
+    fn remove_selected_item(&mut self) {
+        let Some(idx) = self.selected_item() else {
+            return;
+        };
+        let section = self.focused_section;
+        match self.operations_mut(section) {
+            None => {
+                self.transaction.compares.remove(idx);
+            }
+            Some(operations) => {
+                operations.remove(idx);
+            }
+        }
+        let len = self.section_len(section);
+        self.list_state_mut(section)
+            .select(len.checked_sub(1).map(|x| min(idx, x)));
+    }
+
+    fn prompt_execute(&mut self) {
+        let mut popup = ConfirmationPopup::new("Execute transaction?", self.shared_state.clone());
+        popup.show();
+        self.execute_confirmation_popup = Some(popup);
+    }
+
+    fn execute(&mut self) {
+        let transaction = self.transaction.clone();
+        self.execute_task
+            .start(|s| async move { s.execute_txn(&transaction).await });
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    fn outcome_lines(&self) -> Vec<Line<'static>> {
+        let Some((ref transaction, ref outcome)) = self.executed else {
+            return vec![Line::styled("Not executed yet", Style::default().italic())];
+        };
+
+        let (summary, style, operations) = if outcome.succeeded {
+            (
+                "Compares succeeded, success branch ran",
+                Style::default().green(),
+                &transaction.success,
+            )
+        } else {
+            (
+                "Compares failed, failure branch ran",
+                Style::default().yellow(),
+                &transaction.failure,
+            )
+        };
+
+        let mut lines = vec![
+            Line::styled(summary, style),
+            Line::from(format!("Revision {}", outcome.revision)),
+            Line::default(),
+        ];
+        lines.extend(
+            operations
+                .iter()
+                .zip(&outcome.responses)
+                .map(|(op, response)| {
+                    let response = match response {
+                        TxnOpOutcome::Put => "OK".into(),
+                        TxnOpOutcome::Get(Some(x)) => format!(
+                            "\"{}\" (mod revision {}, version {})",
+                            x.value, x.mod_revision, x.version
+                        ),
+                        TxnOpOutcome::Get(None) => "not found".into(),
+                        TxnOpOutcome::Delete(x) => format!("deleted {x} key(s)"),
+                    };
+                    Line::from(format!("{op}: {response}"))
+                }),
+        );
+        lines
+    }
+
+    fn draw_section(&mut self, frame: &mut Frame, rect: Rect, section: Section) {
+        let ran = self
+            .executed
+            .as_ref()
+            .is_some_and(|(_, x)| x.succeeded == (section == Section::Success));
+        let title = match section {
+            Section::Compares => "Compares (all must hold)",
+            Section::Success if ran => "Success operations (ran)",
+            Section::Success => "Success operations",
+            Section::Failure if ran => "Failure operations (ran)",
+            Section::Failure => "Failure operations",
+        };
+        let items = match section {
+            Section::Compares => to_items(&self.transaction.compares),
+            Section::Success => to_items(&self.transaction.success),
+            Section::Failure => to_items(&self.transaction.failure),
+        };
+        let highlight_style = if self.focused_section == section {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+
+        let widget = List::new(items)
+            .block(main_titled_block(title))
+            .highlight_style(highlight_style);
+        frame.render_stateful_widget(widget, rect, self.list_state_mut(section));
+    }
+}
+
+impl Component for TxnBuilder {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            if let Some((_, ref mut x)) = self.item_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.execute_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.execute_task.handle_key_event(event));
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.move_selection(true);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.move_selection(false);
+                }
+                Input { key: Key::Tab, .. } => {
+                    self.focused_section = self.focused_section.next();
+                }
+                Input {
+                    key: Key::Char('a'),
+                    ..
+                } => {
+                    self.prompt_item(None);
+                }
+                Input {
+                    key: Key::Enter | Key::Char('e'),
+                    ..
+                } => {
+                    if let Some(idx) = self.selected_item() {
+                        self.prompt_item(Some(idx));
+                    }
+                }
+                Input {
+                    key: Key::Delete | Key::Char('d'),
+                    ..
+                } => {
+                    self.remove_selected_item();
+                }
+                Input {
+                    key: Key::Char('x'),
+                    ..
+                } => {
+                    self.prompt_execute();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::TxnBuilderDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some((edited, ref mut x)) = self.item_popup {
+            if let Some(result) = x.status() {
+                self.item_popup = None;
+                if let Some(text) = result.into_done() {
+                    self.save_item(edited, &text);
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.execute_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.execute();
+                }
+                self.execute_confirmation_popup = None;
+            }
+        }
+
+        if let Some(result) = self.execute_task.try_ready() {
+            match result {
+                Ok(outcome) => self.executed = Some((self.transaction.clone(), outcome)),
+                Err(err) => self.show_message("Transaction failed", &err.to_string()),
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(50), Constraint::Min(0)])
+                .split(rect);
+            let sections_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    Constraint::Ratio(1, 3),
+                    Constraint::Ratio(1, 3),
+                    Constraint::Ratio(1, 3),
+                ])
+                .split(layout[0]);
+
+            self.draw_section(frame, sections_layout[0], Section::Compares);
+            self.draw_section(frame, sections_layout[1], Section::Success);
+            self.draw_section(frame, sections_layout[2], Section::Failure);
+
+            let outcome_widget =
+                Paragraph::new(self.outcome_lines()).block(main_titled_block("Result"));
+            frame.render_widget(outcome_widget, layout[1]);
+
+            if let Some((_, ref mut x)) = self.item_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.execute_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            self.execute_task.draw(frame, rect);
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if let Some((_, ref x)) = self.item_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.execute_confirmation_popup {
+                return x.context_help();
+            }
+
+            if self.execute_task.is_visible() {
+                return self.execute_task.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            vec![
+                "(Up/Down) scroll list".into(),
+                "(Tab) next section".into(),
+                "(a) add".into(),
+                "(e/Enter) edit".into(),
+                "(d/Del) remove".into(),
+                "(x) execute".into(),
+                "(Esc) return to key selection".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+}
+
+/// Replace item at `idx` or append it, if `idx` is `None`. Returns index of item.
+fn upsert<T>(items: &mut Vec<T>, idx: Option<usize>, item: T) -> usize {
+    match idx {
+        Some(idx) => {
+            items[idx] = item;
+            idx
+        }
+        None => {
+            items.push(item);
+            items.len() - 1
+        }
+    }
+}
+
+fn to_items<T: ToString>(items: &[T]) -> Vec<ListItem<'static>> {
+    items.iter().map(|x| ListItem::new(x.to_string())).collect()
+}
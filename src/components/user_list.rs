@@ -0,0 +1,747 @@
+use std::cmp::min;
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{List, ListItem, ListState},
+};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::User,
+    ui::{main_titled_block, Frame},
+    SharedState,
+};
+
+use super::{
+    Component, ConfirmationPopup, ForegroundTask, InputPopup, MessagePopup, SelectionPopup,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Pane {
+    Users,
+    Roles,
+}
+
+/// What password is input for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PasswordPurpose {
+    NewUser,
+    ChangePassword,
+    /// Reconnect as root after enabling auth. Password cannot be checked before auth is
+    /// enabled.
+    EnableAuth,
+}
+
+/// User, whose password is being input, which is done twice to avoid typos.
+struct PasswordInput {
+    user: String,
+    purpose: PasswordPurpose,
+    /// Password input first time.
+    first_input: Option<String>,
+}
+
+/// Users of etcd auth along with their roles.
+pub struct UserList {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    users: Vec<User>,
+    list_state: ListState,
+    roles_list_state: ListState,
+    focused_pane: Pane,
+
+    load_users_task: ForegroundTask<Result<Vec<User>>>,
+    update_task: ForegroundTask<Result<()>>,
+    load_roles_task: ForegroundTask<Result<Vec<String>>>,
+    check_root_user_task: ForegroundTask<Result<()>>,
+    auth_task: ForegroundTask<Result<()>>,
+
+    name_popup: Option<InputPopup>,
+    password_input: Option<PasswordInput>,
+    password_popup: Option<InputPopup>,
+    /// Roles, which can be granted to selected user.
+    grantable_roles: Vec<String>,
+    role_selection_popup: Option<SelectionPopup>,
+    delete_confirmation_popup: Option<ConfirmationPopup>,
+    revoke_confirmation_popup: Option<ConfirmationPopup>,
+    enable_auth_confirmation_popup: Option<ConfirmationPopup>,
+    disable_auth_confirmation_popup: Option<ConfirmationPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl UserList {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            users: vec![],
+            list_state: ListState::default(),
+            roles_list_state: ListState::default(),
+            focused_pane: Pane::Users,
+
+            load_users_task: ForegroundTask::new("Loading users", shared_state.clone()),
+            update_task: ForegroundTask::new("Updating user", shared_state.clone()),
+            load_roles_task: ForegroundTask::new("Loading roles", shared_state.clone()),
+            check_root_user_task: ForegroundTask::new("Checking user 'root'", shared_state.clone()),
+            auth_task: ForegroundTask::new("Changing auth", shared_state),
+
+            name_popup: None,
+            password_input: None,
+            password_popup: None,
+            grantable_roles: vec![],
+            role_selection_popup: None,
+            delete_confirmation_popup: None,
+            revoke_confirmation_popup: None,
+            enable_auth_confirmation_popup: None,
+            disable_auth_confirmation_popup: None,
+            message_popup: None,
+        }
+    }
+
+    fn selected_user(&self) -> Option<&User> {
+        self.list_state.selected().and_then(|x| self.users.get(x))
+    }
+
+    fn selected_role(&self) -> Option<&String> {
+        let user = self.selected_user()?;
+        self.roles_list_state
+            .selected()
+            .and_then(|x| user.roles.get(x))
+    }
+
+    fn reload_users(&mut self) {
+        self.load_users_task
+            .start(|s| async move { s.load_users().await });
+    }
+
+    fn select_user(&mut self, idx: Option<usize>) {
+        self.list_state.select(idx);
+        let has_roles = self.selected_user().is_some_and(|x| !x.roles.is_empty());
+        self.roles_list_state.select(has_roles.then_some(0));
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let (list_state, len) = match self.focused_pane {
+            Pane::Users => (&self.list_state, self.users.len()),
+            Pane::Roles => (
+                &self.roles_list_state,
+                self.selected_user().map_or(0, |x| x.roles.len()),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        let selected = list_state.selected().map_or(0, |x| {
+            if down {
+                min(x.saturating_add(1), len - 1)
+            } else {
+                x.saturating_sub(1)
+            }
+        });
+        match self.focused_pane {
+            Pane::Users => self.select_user(Some(selected)),
+            Pane::Roles => self.roles_list_state.select(Some(selected)),
+        }
+    }
+
+    fn toggle_focused_pane(&mut self) {
+        self.focused_pane = match self.focused_pane {
+            Pane::Users => Pane::Roles,
+            Pane::Roles => Pane::Users,
+        };
+    }
+
+    fn prompt_name(&mut self) {
+        let mut popup = InputPopup::new("New user", "name", self.shared_state.clone());
+        popup.show();
+        self.name_popup = Some(popup);
+    }
+
+    fn prompt_password(&mut self, input: PasswordInput) {
+        let title = if input.first_input.is_some() {
+            "Repeat password".to_string()
+        } else {
+            format!("Password of '{}'", input.user)
+        };
+        let mut popup =
+            InputPopup::new(title, "password", self.shared_state.clone()).with_hidden_input();
+        popup.show();
+        self.password_popup = Some(popup);
+        self.password_input = Some(input);
+    }
+
+    fn password_done(&mut self, mut input: PasswordInput, password: String) {
+        let Some(ref first_input) = input.first_input else {
+            input.first_input = Some(password);
+            self.prompt_password(input);
+            return;
+        };
+        if *first_input != password {
+            self.show_message("Passwords differ", "Nothing was changed, try again");
+            return;
+        }
+
+        let user = input.user;
+        match input.purpose {
+            PasswordPurpose::NewUser => {
+                self.update_task
+                    .set_description(format!("Adding user '{user}'"));
+                self.update_task
+                    .start(|s| async move { s.add_user(&user, &password).await });
+            }
+            PasswordPurpose::ChangePassword => {
+                self.update_task
+                    .set_description(format!("Changing password of '{user}'"));
+                self.update_task
+                    .start(|s| async move { s.change_password(&user, &password).await });
+            }
+            PasswordPurpose::EnableAuth => self.enable_auth_as(user, password),
+        }
+    }
+
+    fn prompt_delete(&mut self) {
+        if let Some(user) = self.selected_user() {
+            let mut popup = ConfirmationPopup::new(
+                format!("Delete user '{}'?", user.name),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.delete_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn delete_selected_user(&mut self) {
+        if let Some(user) = self.selected_user().map(|x| x.name.clone()) {
+            self.update_task
+                .set_description(format!("Deleting user '{user}'"));
+            self.update_task
+                .start(|s| async move { s.delete_user(&user).await });
+        }
+    }
+
+    fn prompt_grant(&mut self, roles: Vec<String>) {
+        let Some(user) = self.selected_user().cloned() else {
+            return;
+        };
+        self.grantable_roles = roles
+            .into_iter()
+            .filter(|x| !user.roles.contains(x))
+            .collect();
+        if self.grantable_roles.is_empty() {
+            let message = format!("All roles are already granted to '{}'", user.name);
+            self.show_message("Nothing to grant", &message);
+            return;
+        }
+        let mut popup = SelectionPopup::new(
+            format!("Grant role to '{}'", user.name),
+            self.grantable_roles.clone(),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.role_selection_popup = Some(popup);
+    }
+
+    fn grant_role(&mut self, role: String) {
+        if let Some(user) = self.selected_user().map(|x| x.name.clone()) {
+            self.update_task
+                .set_description(format!("Granting role '{role}'"));
+            self.update_task
+                .start(|s| async move { s.grant_role(&user, &role).await });
+        }
+    }
+
+    fn prompt_revoke(&mut self) {
+        if let (Some(user), Some(role)) = (self.selected_user(), self.selected_role()) {
+            let mut popup = ConfirmationPopup::new(
+                format!("Revoke role '{role}' from '{}'?", user.name),
+                self.shared_state.clone(),
+            );
+            popup.show();
+            self.revoke_confirmation_popup = Some(popup);
+        }
+    }
+
+    fn revoke_selected_role(&mut self) {
+        let user = self.selected_user().map(|x| x.name.clone());
+        let role = self.selected_role().cloned();
+        if let (Some(user), Some(role)) = (user, role) {
+            self.update_task
+                .set_description(format!("Revoking role '{role}'"));
+            self.update_task
+                .start(|s| async move { s.revoke_role(&user, &role).await });
+        }
+    }
+
+    fn prompt_enable_auth(&mut self) {
+        let reconnect = match self.shared_state.current_user() {
+            Some(user) => format!("This app reconnects as '{user}'."),
+            None => "This app reconnects as 'root', whose password is asked next. It cannot be \
+                     checked in advance: if it is wrong, this app is locked out and has to be \
+                     restarted with correct credentials."
+                .into(),
+        };
+        let mut popup = ConfirmationPopup::new("Enable auth?", self.shared_state.clone())
+            .with_explanation(format!(
+                "Every client will have to authenticate and is limited to permissions of its \
+                 roles. Connections without credentials, e.g. of other applications, will be \
+                 rejected. {reconnect}"
+            ));
+        popup.show();
+        self.enable_auth_confirmation_popup = Some(popup);
+    }
+
+    fn enable_auth(&mut self) {
+        if self.shared_state.current_user().is_none() {
+            self.prompt_password(PasswordInput {
+                user: "root".into(),
+                purpose: PasswordPurpose::EnableAuth,
+                first_input: None,
+            });
+            return;
+        }
+        self.auth_task.set_description("Enabling auth");
+        self.auth_task
+            .start(|s| async move { s.enable_auth(None).await });
+    }
+
+    fn enable_auth_as(&mut self, user: String, password: String) {
+        self.auth_task.set_description("Enabling auth");
+        self.auth_task
+            .start(|s| async move { s.enable_auth(Some((user, password))).await });
+    }
+
+    fn prompt_disable_auth(&mut self) {
+        let mut popup = ConfirmationPopup::new("Disable auth?", self.shared_state.clone())
+            .with_explanation(
+                "Permissions will no longer be enforced, every client gets full access to all \
+                 keys, even without credentials. This app reconnects without credentials.",
+            );
+        popup.show();
+        self.disable_auth_confirmation_popup = Some(popup);
+    }
+
+    fn disable_auth(&mut self) {
+        self.auth_task.set_description("Disabling auth");
+        self.auth_task
+            .start(|s| async move { s.disable_auth().await });
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+}
+
+impl Component for UserList {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_users_task.handle_key_event(event));
+            key_event!(self.update_task.handle_key_event(event));
+            key_event!(self.load_roles_task.handle_key_event(event));
+            key_event!(self.check_root_user_task.handle_key_event(event));
+            key_event!(self.auth_task.handle_key_event(event));
+            if let Some(ref mut x) = self.name_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.password_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.role_selection_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.delete_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.enable_auth_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.disable_auth_confirmation_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    self.move_selection(true);
+                }
+                Input { key: Key::Up, .. } => {
+                    self.move_selection(false);
+                }
+                Input {
+                    key: Key::Tab | Key::Left | Key::Right,
+                    ..
+                } => {
+                    self.toggle_focused_pane();
+                }
+                Input {
+                    key: Key::Char('a'),
+                    ..
+                } => {
+                    self.prompt_name();
+                }
+                Input {
+                    key: Key::Char('p'),
+                    ..
+                } => {
+                    if let Some(user) = self.selected_user().map(|x| x.name.clone()) {
+                        self.prompt_password(PasswordInput {
+                            user,
+                            purpose: PasswordPurpose::ChangePassword,
+                            first_input: None,
+                        });
+                    }
+                }
+                Input {
+                    key: Key::Char('g'),
+                    ..
+                } if self.selected_user().is_some() => {
+                    self.load_roles_task
+                        .start(|s| async move { s.load_role_names().await });
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } if self.focused_pane == Pane::Roles => {
+                    self.prompt_revoke();
+                }
+                Input {
+                    key: Key::Char('d') | Key::Delete,
+                    ..
+                } if self.focused_pane == Pane::Users => {
+                    self.prompt_delete();
+                }
+                Input {
+                    key: Key::Char('E'),
+                    ..
+                } => {
+                    self.check_root_user_task
+                        .start(|s| async move { s.check_root_user().await });
+                }
+                Input {
+                    key: Key::Char('D'),
+                    ..
+                } => {
+                    self.prompt_disable_auth();
+                }
+                Input {
+                    key: Key::Char('R'),
+                    ..
+                } => {
+                    self.reload_users();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.shared_state.send_event(Event::UsersDone)?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(result) = self.load_users_task.try_ready() {
+            match result {
+                Ok(users) => {
+                    let selected_name = self.selected_user().map(|x| x.name.clone());
+                    self.users = users;
+                    let idx = selected_name
+                        .and_then(|name| self.users.iter().position(|x| x.name == name))
+                        .or((!self.users.is_empty()).then_some(0));
+                    self.select_user(idx);
+                }
+                Err(err) => self.show_message("Cannot load users", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.update_task.try_ready() {
+            if let Err(err) = result {
+                self.show_message("Cannot update user", &err.to_string());
+            }
+            self.reload_users();
+        }
+
+        if let Some(result) = self.load_roles_task.try_ready() {
+            match result {
+                Ok(roles) => self.prompt_grant(roles),
+                Err(err) => self.show_message("Cannot load roles", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.check_root_user_task.try_ready() {
+            match result {
+                Ok(()) => self.prompt_enable_auth(),
+                Err(err) => self.show_message("Cannot enable auth", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.auth_task.try_ready() {
+            match result {
+                Ok(()) => {
+                    let message = match self.shared_state.current_user() {
+                        Some(user) => format!("Auth is enabled, connected as '{user}'"),
+                        None => "Auth is disabled, connected without credentials".into(),
+                    };
+                    self.show_message("Auth changed", &message);
+                }
+                Err(err) => self.show_message("Cannot change auth", &err.to_string()),
+            }
+            self.reload_users();
+        }
+
+        if let Some(ref mut x) = self.name_popup {
+            if let Some(result) = x.status() {
+                if let Some(user) = result.into_done() {
+                    self.prompt_password(PasswordInput {
+                        user,
+                        purpose: PasswordPurpose::NewUser,
+                        first_input: None,
+                    });
+                }
+                self.name_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.password_popup {
+            if let Some(result) = x.status() {
+                self.password_popup = None;
+                let input = self.password_input.take();
+                if let (Some(password), Some(input)) = (result.into_done(), input) {
+                    self.password_done(input, password);
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.role_selection_popup {
+            if let Some(result) = x.status() {
+                let roles = std::mem::take(&mut self.grantable_roles);
+                if let Some(role) = result.into_done().and_then(|x| roles.get(x)) {
+                    self.grant_role(role.clone());
+                }
+                self.role_selection_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.delete_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.delete_selected_user();
+                }
+                self.delete_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.revoke_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.revoke_selected_role();
+                }
+                self.revoke_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.enable_auth_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.enable_auth();
+                }
+                self.enable_auth_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.disable_auth_confirmation_popup {
+            if let Some(result) = x.status() {
+                if result.is_yes() {
+                    self.disable_auth();
+                }
+                self.disable_auth_confirmation_popup = None;
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(40), Constraint::Min(0)])
+                .split(rect);
+
+            let highlight_style = |pane| {
+                if self.focused_pane == pane {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                }
+            };
+
+            let items = self
+                .users
+                .iter()
+                .map(|x| ListItem::new(format!("{} ({} role(s))", x.name, x.roles.len())))
+                .collect::<Vec<_>>();
+            let title = match self.shared_state.current_user() {
+                Some(user) => format!("Users (connected as '{user}')"),
+                None => "Users (connected without credentials)".into(),
+            };
+            let list_widget = List::new(items)
+                .block(main_titled_block(title))
+                .highlight_style(highlight_style(Pane::Users));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (roles_title, roles) = match self.selected_user() {
+                Some(x) => (
+                    format!("Roles of '{}'", x.name),
+                    x.roles.iter().map(|x| ListItem::new(x.clone())).collect(),
+                ),
+                None => ("Roles".into(), vec![]),
+            };
+            let roles_widget = List::new(roles)
+                .block(main_titled_block(roles_title))
+                .highlight_style(highlight_style(Pane::Roles));
+            frame.render_stateful_widget(roles_widget, layout[1], &mut self.roles_list_state);
+
+            self.load_users_task.draw(frame, rect);
+            self.update_task.draw(frame, rect);
+            self.load_roles_task.draw(frame, rect);
+            self.check_root_user_task.draw(frame, rect);
+            self.auth_task.draw(frame, rect);
+            if let Some(ref mut x) = self.name_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.password_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.role_selection_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.delete_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.revoke_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.enable_auth_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.disable_auth_confirmation_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_users_task.is_visible() {
+                return self.load_users_task.context_help();
+            }
+
+            if self.update_task.is_visible() {
+                return self.update_task.context_help();
+            }
+
+            if self.load_roles_task.is_visible() {
+                return self.load_roles_task.context_help();
+            }
+
+            if self.check_root_user_task.is_visible() {
+                return self.check_root_user_task.context_help();
+            }
+
+            if self.auth_task.is_visible() {
+                return self.auth_task.context_help();
+            }
+
+            if let Some(ref x) = self.name_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.password_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.role_selection_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.delete_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.revoke_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.enable_auth_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.disable_auth_confirmation_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            let mut help = vec![
+                "(Up/Down) scroll list".into(),
+                "(Tab) switch between users and roles".into(),
+                "(a) add user".into(),
+                "(p) change password".into(),
+                "(g) grant role".into(),
+            ];
+            match self.focused_pane {
+                Pane::Users => help.push("(d/Del) delete user".into()),
+                Pane::Roles => help.push("(r) revoke role".into()),
+            }
+            help.extend([
+                "(E) enable auth".into(),
+                "(D) disable auth".into(),
+                "(R) reload".into(),
+                "(Esc) return to key selection".into(),
+            ]);
+            help
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    fn show(&mut self) {
+        self.is_visible = true;
+        self.focused_pane = Pane::Users;
+        self.reload_users();
+    }
+}
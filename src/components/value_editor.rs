@@ -1,16 +1,31 @@
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
 
 use crossterm::event::KeyEvent;
-use ratatui::prelude::Rect;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::Paragraph,
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 use tui_textarea::{Input, Key, TextArea};
 
 use crate::{
     events::{Event, KeyEventState},
+    merge::{merge3, CONFLICT_START_MARKER},
+    shared_state::{
+        KeyValue, PutLease, PutResult, StagedChange, WatchEvent, WatchEventKind, WatchUpdate,
+    },
     ui::{main_titled_block, Frame},
+    utils::AsyncTask,
     SharedState,
 };
 
-use super::{confirmation_popup::ConfirmationResult, Component, ConfirmationPopup, ForegroundTask};
+use super::{
+    confirmation_popup::ConfirmationResult, conflict_popup::ConflictResolution, Component,
+    ConflictPopup, DiffPopup, ForegroundTask, InputPopup, MessagePopup,
+};
 
 pub struct ValueEditor {
     shared_state: SharedState,
@@ -18,12 +33,34 @@ pub struct ValueEditor {
     is_visible: bool,
 
     is_in_editing_mode: bool,
+    /// Revision, if key was opened in time-travel mode.
+    historical_revision: Option<i64>,
+    /// Whether current user can read key, but not write it.
+    is_read_only: bool,
     editor_textarea: TextArea<'static>,
     key: String,
     original_key_value: Option<String>,
+    /// Revision of key, when it was loaded. 0 if key didn't exist.
+    mod_revision: i64,
+    /// Latest change of key, made by someone else while it was edited.
+    remote_change: Option<WatchEvent>,
+    /// ID of lease attached to key, when it was loaded. 0 if none.
+    lease_id: i64,
+    /// Time, when lease attached to key expires. `None` until its TTL is loaded.
+    lease_expires_at: Option<Instant>,
+    /// Lease to save key with.
+    lease: PutLease,
 
-    confirmation_popup: Option<ConfirmationPopup>,
-    put_key_task: ForegroundTask<Result<()>>,
+    confirmation_popup: Option<DiffPopup>,
+    conflict_popup: Option<ConflictPopup>,
+    remote_value_popup: Option<DiffPopup>,
+    lease_popup: Option<InputPopup>,
+    message_popup: Option<MessagePopup>,
+    put_key_task: ForegroundTask<Result<PutResult>>,
+    load_remote_task: ForegroundTask<Result<Option<KeyValue>>>,
+    watch_task: AsyncTask<Result<()>>,
+    watch_rx: Option<UnboundedReceiver<WatchUpdate>>,
+    lease_ttl_task: AsyncTask<Result<Option<i64>>>,
 }
 
 impl ValueEditor {
@@ -34,12 +71,27 @@ impl ValueEditor {
             is_visible: false,
 
             is_in_editing_mode: false,
+            historical_revision: None,
+            is_read_only: false,
             editor_textarea: TextArea::new(vec![]),
             key: String::new(),
             original_key_value: None,
+            mod_revision: 0,
+            remote_change: None,
+            lease_id: 0,
+            lease_expires_at: None,
+            lease: PutLease::None,
 
             confirmation_popup: None,
-            put_key_task: ForegroundTask::new("Saving key", shared_state),
+            conflict_popup: None,
+            remote_value_popup: None,
+            lease_popup: None,
+            message_popup: None,
+            put_key_task: ForegroundTask::new("Saving key", shared_state.clone()),
+            load_remote_task: ForegroundTask::new("Loading remote value", shared_state.clone()),
+            watch_task: AsyncTask::new(shared_state.clone()),
+            watch_rx: None,
+            lease_ttl_task: AsyncTask::new(shared_state),
         }
     }
 
@@ -47,50 +99,336 @@ impl ValueEditor {
         self.editor_textarea.lines().join("\n")
     }
 
-    pub fn open_key(&mut self, key: String, value: Option<String>) {
-        let sanitized_value = value.map(|x| x.replace("\r\n", "\n").replace('\r', "\n"));
+    pub fn open_key(&mut self, key: String, value: Option<KeyValue>) {
+        self.historical_revision = self.shared_state.historical_revision();
+        self.is_read_only = false;
+        self.mod_revision = value.as_ref().map_or(0, |x| x.mod_revision);
+        self.lease_id = value.as_ref().map_or(0, |x| x.lease);
+        self.lease = self.default_lease();
+        self.lease_expires_at = None;
+        let sanitized_value = value.map(|x| sanitize_value(&x.value));
         self.key = key;
-        self.editor_textarea = if let Some(x) = sanitized_value.as_deref() {
-            TextArea::from(x.split('\n'))
-        } else {
-            TextArea::new(vec![])
+        let staged_value = self
+            .historical_revision
+            .is_none()
+            .then(|| self.shared_state.staged_value(&self.key))
+            .flatten();
+        self.editor_textarea = match staged_value.as_deref().or(sanitized_value.as_deref()) {
+            Some(x) => TextArea::from(x.split('\n')),
+            None => TextArea::new(vec![]),
         };
         self.original_key_value = sanitized_value;
+        self.remote_change = None;
 
+        if self.historical_revision.is_none() {
+            self.watch_key();
+            if self.lease_id != 0 {
+                let id = self.lease_id;
+                self.lease_ttl_task
+                    .start(move |s| async move { s.lease_ttl(id).await });
+            }
+        }
         self.show();
     }
 
+    /// Show opened key without allowing to change it, as current user cannot write it.
+    pub fn set_read_only(&mut self) {
+        self.is_read_only = true;
+    }
+
+    /// Whether key can be viewed only, e.g. in time-travel mode.
+    fn is_view_only(&self) -> bool {
+        self.historical_revision.is_some() || self.is_read_only
+    }
+
+    /// Open key, which doesn't exist yet, to save it with `lease`.
+    pub fn open_new_key(&mut self, key: String, lease: PutLease) {
+        self.open_key(key, None);
+        self.lease = lease;
+    }
+
+    /// Lease choice, which leaves lease of key as it is.
+    fn default_lease(&self) -> PutLease {
+        if self.lease_id != 0 {
+            PutLease::Keep
+        } else {
+            PutLease::None
+        }
+    }
+
+    fn prompt_lease(&mut self) {
+        let mut popup = InputPopup::new(
+            "Lease",
+            "TTL (e.g. 30s), lease ID (e.g. 0x694d...), keep or none",
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.lease_popup = Some(popup);
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    /// Watch for changes of opened key, made after it was loaded.
+    fn watch_key(&mut self) {
+        let key = self.key.clone();
+        let start_revision = if self.mod_revision > 0 {
+            self.mod_revision + 1
+        } else {
+            0
+        };
+        let (tx, rx) = unbounded_channel();
+        self.watch_rx = Some(rx);
+        self.watch_task
+            .start(move |s| async move { s.watch(key, false, start_revision, tx).await });
+    }
+
+    fn stop_watching_key(&mut self) {
+        self.watch_task.abort();
+        self.watch_rx = None;
+    }
+
+    fn remote_value(&self) -> Option<&str> {
+        self.remote_change.as_ref().and_then(|x| x.value.as_deref())
+    }
+
+    fn show_remote_value(&mut self) {
+        let Some(ref change) = self.remote_change else {
+            return;
+        };
+        let mut popup = DiffPopup::text_view(
+            format!("Remote value at revision {}", change.revision),
+            self.remote_value().unwrap_or("(deleted)"),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.remote_value_popup = Some(popup);
+    }
+
+    fn show_remote_diff(&mut self) {
+        let Some(ref change) = self.remote_change else {
+            return;
+        };
+        let mut popup = DiffPopup::view_only(
+            format!(
+                "Diff of local value and remote revision {}",
+                change.revision
+            ),
+            &self.editor_content(),
+            self.remote_value().unwrap_or_default(),
+            self.shared_state.clone(),
+        );
+        popup.show();
+        self.remote_value_popup = Some(popup);
+    }
+
+    /// Load result of external edit into editor and ask to save it, if value was changed.
+    ///
+    /// Editor keeps its content, if external edit failed.
+    pub fn external_edit_done(&mut self, result: Result<Option<String>>) {
+        match result {
+            Ok(Some(x)) => {
+                self.editor_textarea = TextArea::from(sanitize_value(&x).split('\n'));
+                if self.has_changes() {
+                    self.prompt_save();
+                }
+            }
+            Ok(None) => {}
+            Err(err) => self.show_message("Cannot edit externally", &format!("{err:#}")),
+        }
+    }
+
+    fn edit_externally(&self) -> Result<()> {
+        self.shared_state
+            .send_event(Event::EditExternally(self.editor_content()))
+    }
+
+    /// Numbers of lines, starting unresolved conflicts of merge, counted from 1.
+    fn conflict_marker_lines(&self) -> Vec<usize> {
+        self.editor_textarea
+            .lines()
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| *x == CONFLICT_START_MARKER)
+            .map(|(idx, _)| idx + 1)
+            .collect()
+    }
+
     fn put_key(&mut self) {
+        let conflict_lines = self.conflict_marker_lines();
+        if !conflict_lines.is_empty() {
+            let lines = conflict_lines
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let message = format!(
+                "Value contains unresolved merge conflicts, starting with '{CONFLICT_START_MARKER}' \
+                 at line(s) {}. Resolve them before saving.",
+                lines.join(", ")
+            );
+            self.show_message("Cannot save key", &message);
+            return;
+        }
+
         let key = self.key.clone();
         let value = self.editor_content();
-        self.put_key_task
-            .start(|s| async move { s.put_key(&key, value).await });
+        let mod_revision = self.mod_revision;
+        let lease = self.lease;
+        if self.shared_state.is_staging() {
+            let change = StagedChange::Put {
+                key,
+                value,
+                old_value: self.original_key_value.clone(),
+                mod_revision,
+                lease,
+            };
+            self.put_key_task.start(move |s| async move {
+                s.stage(change)?;
+                Ok(PutResult::Done)
+            });
+        } else {
+            self.put_key_task
+                .start(move |s| async move { s.put_key(&key, value, mod_revision, lease).await });
+        }
+    }
+
+    fn prompt_conflict_resolution(&mut self, remote: Option<KeyValue>) {
+        let mut popup = ConflictPopup::new(remote, self.shared_state.clone());
+        popup.show();
+        self.conflict_popup = Some(popup);
+    }
+
+    fn resolve_conflict(&mut self, resolution: ConflictResolution, remote: Option<KeyValue>) {
+        match resolution {
+            ConflictResolution::Overwrite => {
+                self.mod_revision = remote.map_or(0, |x| x.mod_revision);
+                self.remote_change = None;
+                self.put_key();
+            }
+            ConflictResolution::Reload => {
+                self.open_key(self.key.clone(), remote);
+            }
+            ConflictResolution::Merge => {
+                // line endings must match those of editor content, so lines can be compared
+                let remote_value = remote.as_ref().map(|x| sanitize_value(&x.value));
+                let merged = merge3(
+                    self.original_key_value.as_deref().unwrap_or_default(),
+                    &self.editor_content(),
+                    remote_value.as_deref().unwrap_or_default(),
+                );
+                self.editor_textarea = TextArea::from(merged.split('\n'));
+                self.mod_revision = remote.map_or(0, |x| x.mod_revision);
+                self.original_key_value = remote_value;
+                self.remote_change = None;
+            }
+            ConflictResolution::Cancel => {}
+        }
     }
 
     fn value_has_changed(&self) -> bool {
         Some(self.editor_content()) != self.original_key_value
     }
 
+    fn has_changes(&self) -> bool {
+        self.value_has_changed() || self.lease != self.default_lease()
+    }
+
+    fn lease_status(&self) -> Option<String> {
+        if self.lease_id == 0 {
+            return None;
+        }
+        let status = match self.lease_expires_at {
+            Some(x) => match x.saturating_duration_since(Instant::now()).as_secs() {
+                0 => format!("lease {:#x} expired", self.lease_id),
+                ttl => format!("lease {:#x} expires in {ttl}s", self.lease_id),
+            },
+            None => format!("lease {:#x}", self.lease_id),
+        };
+        Some(status)
+    }
+
     fn title_status(&self) -> String {
+        if let Some(x) = self.historical_revision {
+            return format!("revision {x}, read-only");
+        }
+        if self.is_read_only {
+            return "read-only for current user".into();
+        }
+
         let mode = if self.is_in_editing_mode {
             "editing"
         } else {
             "not editing"
         };
-        let changed = if self.value_has_changed() {
+        let changed = if self.has_changes() {
             "changed"
         } else {
             "not changed"
         };
-        format!("{mode}, {changed}")
+        let mut status = format!("{mode}, {changed}");
+        if let Some(x) = self.lease_status() {
+            status.push_str(&format!(", {x}"));
+        }
+        if self.lease != self.default_lease() {
+            status.push_str(&format!(", save with {}", self.lease));
+        }
+        if self.remote_change.is_some() {
+            status.push_str(", conflicted");
+        }
+        if !self.conflict_marker_lines().is_empty() {
+            status.push_str(", has merge conflicts");
+        }
+        status
+    }
+
+    fn remote_change_banner(&self) -> Option<Paragraph<'static>> {
+        let change = self.remote_change.as_ref()?;
+        let action = match change.kind {
+            WatchEventKind::Put => "modified",
+            WatchEventKind::Delete => "deleted",
+        };
+        let text = format!(
+            "Key was {action} remotely at revision {}: (v) view remote value, (D) diff with remote",
+            change.revision
+        );
+        Some(Paragraph::new(text).style(Style::default().black().on_yellow()))
     }
 
     fn edit_done(&self) -> Result<()> {
         self.shared_state.send_event(Event::KeyEditDone)
     }
 
+    /// Ask to save changes. If key was changed remotely, decision on conflict is required
+    /// instead.
+    fn prompt_save(&mut self) {
+        if self.remote_change.is_some() {
+            let key = self.key.clone();
+            self.load_remote_task
+                .start(|s| async move { s.find_key(&key).await });
+        } else {
+            self.prompt_save_confirmation();
+        }
+    }
+
     fn prompt_save_confirmation(&mut self) {
-        let mut popup = ConfirmationPopup::new("Save key?", self.shared_state.clone());
+        let mut popup = DiffPopup::new(
+            format!(
+                "{} key '{}' with {}?",
+                if self.shared_state.is_staging() {
+                    "Stage changes of"
+                } else {
+                    "Save"
+                },
+                self.key,
+                self.lease
+            ),
+            self.original_key_value.as_deref().unwrap_or_default(),
+            &self.editor_content(),
+            self.shared_state.clone(),
+        );
         popup.show();
         self.confirmation_popup = Some(popup);
     }
@@ -103,6 +441,19 @@ impl Component for ValueEditor {
             if let Some(ref mut x) = self.confirmation_popup {
                 key_event!(x.handle_key_event(event));
             }
+            if let Some(ref mut x) = self.conflict_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            key_event!(self.load_remote_task.handle_key_event(event));
+            if let Some(ref mut x) = self.remote_value_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.lease_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
 
             if self.is_in_editing_mode {
                 match event.into() {
@@ -118,12 +469,36 @@ impl Component for ValueEditor {
                     Input {
                         key: Key::Enter | Key::Char('e'),
                         ..
-                    } => {
+                    } if !self.is_view_only() => {
                         self.is_in_editing_mode = true;
                     }
+                    Input {
+                        key: Key::Char('E'),
+                        ..
+                    } if !self.is_view_only() => {
+                        self.edit_externally()?;
+                    }
+                    Input {
+                        key: Key::Char('l'),
+                        ..
+                    } if !self.is_view_only() => {
+                        self.prompt_lease();
+                    }
+                    Input {
+                        key: Key::Char('v'),
+                        ..
+                    } => {
+                        self.show_remote_value();
+                    }
+                    Input {
+                        key: Key::Char('D'),
+                        ..
+                    } => {
+                        self.show_remote_diff();
+                    }
                     Input { key: Key::Esc, .. } => {
-                        if self.value_has_changed() {
-                            self.prompt_save_confirmation();
+                        if self.has_changes() {
+                            self.prompt_save();
                         } else {
                             self.edit_done()?;
                         }
@@ -153,9 +528,71 @@ impl Component for ValueEditor {
             }
         }
 
+        if let Some(ref mut x) = self.conflict_popup {
+            if let Some(result) = x.status() {
+                let remote = x.remote().cloned();
+                self.conflict_popup = None;
+                self.resolve_conflict(result, remote);
+            }
+        }
+
         if let Some(result) = self.put_key_task.try_ready() {
-            result?;
-            self.edit_done()?;
+            match result {
+                Ok(PutResult::Done) => self.edit_done()?,
+                Ok(PutResult::Conflict(remote)) => self.prompt_conflict_resolution(remote),
+                Err(err) => self.show_message("Cannot save key", &err.to_string()),
+            }
+        }
+
+        if let Some(result) = self.load_remote_task.try_ready() {
+            match result {
+                Ok(remote) => self.prompt_conflict_resolution(remote),
+                Err(err) => self.show_message("Cannot load remote value", &err.to_string()),
+            }
+        }
+
+        if let Some(ref mut x) = self.remote_value_popup {
+            if x.status().is_some() {
+                self.remote_value_popup = None;
+            }
+        }
+
+        if let Some(Err(err)) = self.watch_task.try_ready() {
+            self.show_message("Cannot watch key for changes", &err.to_string());
+        }
+
+        // lease status is only informational, so it's left out if TTL cannot be loaded
+        if let Some(Ok(ttl)) = self.lease_ttl_task.try_ready() {
+            let ttl = ttl.unwrap_or_default();
+            self.lease_expires_at = Some(Instant::now() + Duration::from_secs(ttl as u64));
+        }
+
+        if let Some(ref mut x) = self.lease_popup {
+            if let Some(result) = x.status() {
+                self.lease_popup = None;
+                if let Some(lease) = result.into_done() {
+                    match lease.parse() {
+                        Ok(x) => self.lease = x,
+                        Err(err) => self.show_message("Error", &err.to_string()),
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        if let Some(ref mut rx) = self.watch_rx {
+            while let Ok(update) = rx.try_recv() {
+                if let WatchUpdate::Event(x) = update {
+                    if x.revision > self.mod_revision {
+                        self.remote_change = Some(x);
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -167,17 +604,45 @@ impl Component for ValueEditor {
             self.editor_textarea
                 .set_block(main_titled_block(value_editor_title));
 
-            frame.render_widget(self.editor_textarea.widget(), rect);
+            let editor_rect = match self.remote_change_banner() {
+                Some(banner) => {
+                    let layout = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(vec![Constraint::Max(1), Constraint::Min(0)])
+                        .split(rect);
+                    frame.render_widget(banner, layout[0]);
+                    layout[1]
+                }
+                None => rect,
+            };
+            frame.render_widget(self.editor_textarea.widget(), editor_rect);
 
             self.put_key_task.draw(frame, rect);
+            self.load_remote_task.draw(frame, rect);
             if let Some(ref mut x) = self.confirmation_popup {
                 x.draw(frame, rect);
             }
+            if let Some(ref mut x) = self.conflict_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.remote_value_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.lease_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
         }
     }
 
     fn set_visibility(&mut self, value: bool) {
         self.is_visible = value;
+        if !value {
+            self.stop_watching_key();
+            self.lease_ttl_task.abort();
+        }
     }
 
     fn is_visible(&self) -> bool {
@@ -190,16 +655,54 @@ impl Component for ValueEditor {
                 return x.context_help();
             }
 
+            if let Some(ref x) = self.conflict_popup {
+                return x.context_help();
+            }
+
+            if self.load_remote_task.is_visible() {
+                return self.load_remote_task.context_help();
+            }
+
+            if let Some(ref x) = self.remote_value_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.lease_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
             if self.is_in_editing_mode {
                 vec!["(Esc) exit editing mode".into()]
+            } else if self.historical_revision.is_some() {
+                vec!["(Esc) return to key selection".into()]
             } else {
-                vec![
-                    "(e/Enter) enter editing mode".into(),
-                    "(Esc) return to key selection".into(),
-                ]
+                let mut help: Vec<String> = vec![];
+                if !self.is_read_only {
+                    help.extend([
+                        "(e/Enter) enter editing mode".into(),
+                        "(E) edit in $EDITOR".into(),
+                        "(l) set lease".into(),
+                    ]);
+                }
+                if self.remote_change.is_some() {
+                    help.extend([
+                        "(v) view remote value".into(),
+                        "(D) diff with remote".into(),
+                    ]);
+                }
+                help.push("(Esc) return to key selection".into());
+                help
             }
         } else {
             vec![]
         }
     }
 }
+
+fn sanitize_value(value: &str) -> String {
+    value.replace("\r\n", "\n").replace('\r', "\n")
+}
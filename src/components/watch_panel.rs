@@ -0,0 +1,574 @@
+use std::{
+    cmp::min,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    events::{Event, KeyEventState},
+    shared_state::{WatchEvent, WatchEventKind, WatchUpdate},
+    ui::{main_titled_block, unified_diff_lines, Frame},
+    utils::AsyncTask,
+    watch_record::{load_recording, WatchRecorder},
+    SharedState,
+};
+
+use super::{Component, ForegroundTask, InputPopup, MessagePopup};
+
+const DEFAULT_RECORD_PATH: &str = "etcd-watch.jsonl";
+/// Maximum number of entries kept in log and received while paused, older ones are dropped.
+const MAX_LOG_LEN: usize = 10_000;
+
+/// Recorded events, which are stepped through instead of live watch.
+struct Replay {
+    path: PathBuf,
+    events: Vec<WatchEvent>,
+}
+
+/// Live log of changes of keys with some prefix.
+///
+/// Also used to replay events recorded to file.
+pub struct WatchPanel {
+    shared_state: SharedState,
+
+    is_visible: bool,
+
+    prefix: String,
+    log: VecDeque<WatchUpdate>,
+    /// Number of entries dropped from start of log. During replay it's also index of first
+    /// event in log.
+    dropped: usize,
+    /// Updates, received while paused.
+    pending: VecDeque<WatchUpdate>,
+    /// Number of updates dropped from start of `pending`.
+    pending_dropped: usize,
+    is_paused: bool,
+    filter: Option<String>,
+    list_state: ListState,
+    recorder: Option<WatchRecorder>,
+    replay: Option<Replay>,
+
+    watch_task: AsyncTask<Result<()>>,
+    updates_rx: Option<UnboundedReceiver<WatchUpdate>>,
+    load_replay_task: ForegroundTask<Result<(PathBuf, Vec<WatchEvent>)>>,
+    filter_popup: Option<InputPopup>,
+    record_popup: Option<InputPopup>,
+    message_popup: Option<MessagePopup>,
+}
+
+impl WatchPanel {
+    pub fn new(shared_state: SharedState) -> Self {
+        Self {
+            shared_state: shared_state.clone(),
+
+            is_visible: false,
+
+            prefix: String::new(),
+            log: VecDeque::new(),
+            dropped: 0,
+            pending: VecDeque::new(),
+            pending_dropped: 0,
+            is_paused: false,
+            filter: None,
+            list_state: ListState::default(),
+            recorder: None,
+            replay: None,
+
+            watch_task: AsyncTask::new(shared_state.clone()),
+            updates_rx: None,
+            load_replay_task: ForegroundTask::new("Loading recording", shared_state),
+            filter_popup: None,
+            record_popup: None,
+            message_popup: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+        self.is_paused = false;
+        self.filter = None;
+        self.recorder = None;
+        self.replay = None;
+    }
+
+    pub fn open_prefix(&mut self, prefix: String) {
+        self.reset();
+        self.prefix = prefix.clone();
+
+        let (tx, rx) = unbounded_channel();
+        self.updates_rx = Some(rx);
+        self.watch_task
+            .start(|s| async move { s.watch(prefix, true, 0, tx).await });
+
+        if let Some(path) = self.shared_state.cli().record.clone() {
+            self.start_recording(&path);
+        }
+
+        self.show();
+    }
+
+    pub fn open_replay(&mut self, path: PathBuf) {
+        self.reset();
+        self.load_replay_task.start(|_| async move {
+            let events = load_recording(&path)?;
+            Ok((path, events))
+        });
+        self.show();
+    }
+
+    fn start_recording(&mut self, path: &Path) {
+        match WatchRecorder::create(path) {
+            Ok(x) => self.recorder = Some(x),
+            Err(err) => self.show_message("Cannot record events", &format!("{err:#}")),
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        if self.recorder.take().is_some() {
+            return;
+        }
+        let default_path = self
+            .shared_state
+            .cli()
+            .record
+            .clone()
+            .unwrap_or_else(|| DEFAULT_RECORD_PATH.into());
+        let mut popup = InputPopup::new(
+            "Record events to file",
+            "Enter file path",
+            self.shared_state.clone(),
+        )
+        .with_value(&default_path.to_string_lossy());
+        popup.show();
+        self.record_popup = Some(popup);
+    }
+
+    fn record(&mut self, updates: &[WatchUpdate]) {
+        let Some(ref mut recorder) = self.recorder else {
+            return;
+        };
+        let events = updates.iter().filter_map(|x| match x {
+            WatchUpdate::Event(event) => Some(event),
+            WatchUpdate::Disconnected(_) => None,
+        });
+        if let Err(err) = recorder.record(events) {
+            self.recorder = None;
+            self.show_message("Recording stopped", &format!("{err:#}"));
+        }
+    }
+
+    /// Move replay position forward (`true`) or backward (`false`) by one event.
+    fn step_replay(&mut self, forward: bool) {
+        let Some(ref replay) = self.replay else {
+            return;
+        };
+        if forward {
+            if let Some(x) = replay.events.get(self.dropped + self.log.len()) {
+                let update = WatchUpdate::Event(x.clone());
+                self.log.push_back(update);
+                self.trim_log();
+            }
+        } else if self.log.pop_back().is_some() && self.dropped > 0 {
+            // keep log full, so stepping back never runs out of events
+            self.dropped -= 1;
+            let update = WatchUpdate::Event(replay.events[self.dropped].clone());
+            self.log.push_front(update);
+        }
+        self.select_last();
+    }
+
+    fn show_message(&mut self, title: &str, message: &str) {
+        let mut popup = MessagePopup::new(title, message, self.shared_state.clone());
+        popup.show();
+        self.message_popup = Some(popup);
+    }
+
+    fn is_shown(&self, update: &WatchUpdate) -> bool {
+        match (update, &self.filter) {
+            (WatchUpdate::Event(event), Some(filter)) => event.key.contains(filter.as_str()),
+            _ => true,
+        }
+    }
+
+    fn visible_log(&self) -> Vec<&WatchUpdate> {
+        self.log.iter().filter(|x| self.is_shown(x)).collect()
+    }
+
+    /// Drop oldest entries over `MAX_LOG_LEN`, keeping selection on the same entry.
+    fn trim_log(&mut self) {
+        let mut removed = 0;
+        while self.log.len() > MAX_LOG_LEN {
+            if let Some(x) = self.log.pop_front() {
+                if self.is_shown(&x) {
+                    removed += 1;
+                }
+                self.dropped += 1;
+            }
+        }
+        if removed > 0 {
+            if let Some(x) = self.list_state.selected() {
+                self.list_state.select(Some(x.saturating_sub(removed)));
+            }
+        }
+    }
+
+    fn selected_event(&self) -> Option<&WatchEvent> {
+        let selected = self.list_state.selected()?;
+        match self.visible_log().get(selected) {
+            Some(WatchUpdate::Event(x)) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Whether last log entry is selected, so new entries should be followed.
+    fn is_following(&self) -> bool {
+        self.list_state
+            .selected()
+            .is_none_or(|x| x + 1 >= self.visible_log().len())
+    }
+
+    fn append(&mut self, updates: impl IntoIterator<Item = WatchUpdate>) {
+        let is_following = self.is_following();
+        let len = self.log.len();
+        self.log.extend(updates);
+        if self.log.len() == len {
+            return;
+        }
+        self.trim_log();
+        if is_following {
+            self.select_last();
+        }
+    }
+
+    fn select_last(&mut self) {
+        let len = self.visible_log().len();
+        self.list_state.select(len.checked_sub(1));
+    }
+
+    fn toggle_pause(&mut self) {
+        self.is_paused = !self.is_paused;
+        if !self.is_paused {
+            let pending = std::mem::take(&mut self.pending);
+            self.dropped += std::mem::take(&mut self.pending_dropped);
+            self.append(pending);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.log.clear();
+        self.dropped = 0;
+        self.pending.clear();
+        self.pending_dropped = 0;
+        self.list_state.select(None);
+    }
+
+    fn prompt_filter(&mut self) {
+        let mut popup = InputPopup::new(
+            "Filter keys",
+            "Substring of key, Esc to reset",
+            self.shared_state.clone(),
+        )
+        .with_value(self.filter.as_deref().unwrap_or_default());
+        popup.show();
+        self.filter_popup = Some(popup);
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.watch_task.abort();
+        self.updates_rx = None;
+        self.shared_state.send_event(Event::WatchDone)
+    }
+
+    fn title(&self) -> String {
+        let mut title = match self.replay {
+            Some(ref x) => format!(
+                "Replaying '{}' ({}/{})",
+                x.path.display(),
+                self.dropped + self.log.len(),
+                x.events.len()
+            ),
+            None => format!("Watching '{}'", self.prefix),
+        };
+        if let Some(ref x) = self.recorder {
+            title.push_str(&format!(", recording to '{}'", x.path().display()));
+        }
+        if let Some(ref x) = self.filter {
+            title.push_str(&format!(", filter '{x}'"));
+        }
+        if self.dropped > 0 {
+            title.push_str(&format!(", {} older entries dropped", self.dropped));
+        }
+        if self.is_paused {
+            title.push_str(&format!(", paused ({} pending", self.pending.len()));
+            if self.pending_dropped > 0 {
+                title.push_str(&format!(", {} dropped", self.pending_dropped));
+            }
+            title.push(')');
+        }
+        title
+    }
+}
+
+fn log_line(update: &WatchUpdate) -> Line<'static> {
+    match update {
+        WatchUpdate::Event(x) => {
+            let (kind, style) = match x.kind {
+                WatchEventKind::Put => ("PUT", Style::default().green()),
+                WatchEventKind::Delete => ("DELETE", Style::default().red()),
+            };
+            Line::styled(format!("[{}] {kind:<6} {}", x.revision, x.key), style)
+        }
+        WatchUpdate::Disconnected(x) => Line::styled(
+            format!("Disconnected, reconnecting: {x}"),
+            Style::default().yellow(),
+        ),
+    }
+}
+
+impl Component for WatchPanel {
+    fn handle_key_event(&mut self, event: KeyEvent) -> Result<KeyEventState> {
+        if self.is_visible() {
+            key_event!(self.load_replay_task.handle_key_event(event));
+            if let Some(ref mut x) = self.filter_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.record_popup {
+                key_event!(x.handle_key_event(event));
+            }
+            if let Some(ref mut x) = self.message_popup {
+                key_event!(x.handle_key_event(event));
+            }
+
+            let is_replay = self.replay.is_some();
+            let selected_id = self.list_state.selected();
+            match event.into() {
+                Input { key: Key::Down, .. } => {
+                    let len = self.visible_log().len();
+                    self.list_state.select(Some(
+                        selected_id.map_or(0, |x| min(x.saturating_add(1), len.saturating_sub(1))),
+                    ));
+                }
+                Input { key: Key::Up, .. } => {
+                    self.list_state
+                        .select(Some(selected_id.map_or(0, |x| x.saturating_sub(1))));
+                }
+                Input { key: Key::End, .. } => {
+                    self.select_last();
+                }
+                Input {
+                    key: Key::Right | Key::Char('n'),
+                    ..
+                } if is_replay => {
+                    self.step_replay(true);
+                }
+                Input {
+                    key: Key::Left | Key::Char('b'),
+                    ..
+                } if is_replay => {
+                    self.step_replay(false);
+                }
+                Input {
+                    key: Key::Char('p'),
+                    ..
+                } if !is_replay => {
+                    self.toggle_pause();
+                }
+                Input {
+                    key: Key::Char('c'),
+                    ..
+                } if !is_replay => {
+                    self.clear();
+                }
+                Input {
+                    key: Key::Char('r'),
+                    ..
+                } if !is_replay => {
+                    self.toggle_recording();
+                }
+                Input {
+                    key: Key::Char('/'),
+                    ..
+                } => {
+                    self.prompt_filter();
+                }
+                Input { key: Key::Esc, .. } => {
+                    self.close()?;
+                }
+                _ => {}
+            }
+            Ok(KeyEventState::Consumed)
+        } else {
+            Ok(KeyEventState::NotConsumed)
+        }
+    }
+
+    fn update(&mut self) -> Result<()> {
+        if let Some(Err(err)) = self.watch_task.try_ready() {
+            self.show_message("Watch failed", &format!("{err:#}"));
+        }
+
+        if let Some(result) = self.load_replay_task.try_ready() {
+            match result {
+                Ok((path, events)) => {
+                    self.replay = Some(Replay { path, events });
+                    self.step_replay(true);
+                }
+                Err(err) => self.show_message("Cannot load recording", &format!("{err:#}")),
+            }
+        }
+
+        let mut updates = vec![];
+        if let Some(ref mut rx) = self.updates_rx {
+            while let Ok(x) = rx.try_recv() {
+                updates.push(x);
+            }
+        }
+        self.record(&updates);
+        if self.is_paused {
+            self.pending.extend(updates);
+            while self.pending.len() > MAX_LOG_LEN {
+                self.pending.pop_front();
+                self.pending_dropped += 1;
+            }
+        } else {
+            self.append(updates);
+        }
+
+        if let Some(ref mut x) = self.filter_popup {
+            if let Some(result) = x.status() {
+                self.filter = result.into_done();
+                self.filter_popup = None;
+                self.select_last();
+            }
+        }
+
+        if let Some(ref mut x) = self.record_popup {
+            if let Some(result) = x.status() {
+                self.record_popup = None;
+                if let Some(path) = result.into_done() {
+                    self.start_recording(Path::new(&path));
+                }
+            }
+        }
+
+        if let Some(ref mut x) = self.message_popup {
+            if x.is_closed() {
+                self.message_popup = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if self.is_visible() {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Percentage(60), Constraint::Min(0)])
+                .split(rect);
+
+            let items = self
+                .visible_log()
+                .into_iter()
+                .map(|x| ListItem::new(log_line(x)))
+                .collect::<Vec<_>>();
+            let list_widget = List::new(items)
+                .block(main_titled_block(self.title()))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list_widget, layout[0], &mut self.list_state);
+
+            let (diff_title, diff) = match self.selected_event() {
+                Some(x) => (
+                    format!("Value diff ({})", humantime::format_rfc3339_millis(x.time)),
+                    unified_diff_lines(
+                        x.prev_value.as_deref().unwrap_or_default(),
+                        x.value.as_deref().unwrap_or_default(),
+                    ),
+                ),
+                None => ("Value diff".into(), vec![]),
+            };
+            let diff_widget = Paragraph::new(diff).block(main_titled_block(diff_title));
+            frame.render_widget(diff_widget, layout[1]);
+
+            self.load_replay_task.draw(frame, rect);
+            if let Some(ref mut x) = self.filter_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.record_popup {
+                x.draw(frame, rect);
+            }
+            if let Some(ref mut x) = self.message_popup {
+                x.draw(frame, rect);
+            }
+        }
+    }
+
+    fn context_help(&self) -> Vec<String> {
+        if self.is_visible() {
+            if self.load_replay_task.is_visible() {
+                return self.load_replay_task.context_help();
+            }
+
+            if let Some(ref x) = self.filter_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.record_popup {
+                return x.context_help();
+            }
+
+            if let Some(ref x) = self.message_popup {
+                return x.context_help();
+            }
+
+            if self.replay.is_some() {
+                return vec![
+                    "(Up/Down) scroll log".into(),
+                    "(Right/n) next event".into(),
+                    "(Left/b) previous event".into(),
+                    "(/) filter".into(),
+                    "(Esc) stop replay".into(),
+                ];
+            }
+
+            vec![
+                "(Up/Down) scroll log".into(),
+                "(End) follow new events".into(),
+                if self.is_paused {
+                    "(p) resume".into()
+                } else {
+                    "(p) pause".into()
+                },
+                "(c) clear".into(),
+                "(/) filter".into(),
+                if self.recorder.is_some() {
+                    "(r) stop recording".into()
+                } else {
+                    "(r) record to file".into()
+                },
+                "(Esc) stop watching".into(),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_visibility(&mut self, value: bool) {
+        self.is_visible = value;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+}
@@ -5,7 +5,10 @@ use crossterm::event::{Event as CrosstermEvent, EventStream, KeyEvent};
 use futures::StreamExt;
 use tokio::time::{interval, MissedTickBehavior};
 
-use crate::SharedState;
+use crate::{
+    shared_state::{KeyValue, PutLease},
+    SharedState,
+};
 
 macro_rules! key_event {
     ($x:expr) => {
@@ -23,9 +26,51 @@ pub enum KeyEventState {
 pub enum Event {
     Tick,
     Keyboard(KeyEvent),
-    KeySelected { key: String, value: Option<String> },
+    KeySelected {
+        key: String,
+        value: Option<KeyValue>,
+        /// Whether current user can read key, but not write it.
+        is_read_only: bool,
+    },
+    /// Create new key, saving it with `lease`.
+    NewKey {
+        key: String,
+        lease: PutLease,
+    },
     KeyEditDone,
-    Tui(CrosstermEvent),
+    ShowKeyHistory(String),
+    KeyHistoryDone,
+    ShowWatch(String),
+    WatchDone,
+    ShowLeases,
+    LeasesDone,
+    ShowStagingReview,
+    StagingReviewDone,
+    ShowTxnBuilder,
+    TxnBuilderDone,
+    /// Show cluster view, with key selected in key selection, if any.
+    ShowCluster(Option<String>),
+    ClusterDone,
+    ShowAlarms,
+    AlarmsDone,
+    ShowUsers,
+    UsersDone,
+    /// Show roles, with key selected in key selection, if any.
+    ShowRoles(Option<String>),
+    RolesDone,
+    /// Show permissions of current user, evaluated for key selected in key selection, if any.
+    ShowPermissions(Option<String>),
+    PermissionsDone,
+    /// Show locks, with key selected in key selection, if any.
+    ShowLocks(Option<String>),
+    LocksDone,
+    /// Return to key selection with given key selected.
+    RevealKey(String),
+    /// Suspend TUI and edit value in external editor.
+    EditExternally(String),
+    /// Result of external edit, `None` if editing was cancelled.
+    ExternalEditDone(Result<Option<String>>),
+    Tui(#[allow(unused)] CrosstermEvent),
     Quit(Result<()>),
 }
 
@@ -0,0 +1,90 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Number of attempts to create temporary file with unused random name.
+const TEMP_FILE_ATTEMPTS: usize = 8;
+
+/// Edit `value` in user's `$VISUAL`/`$EDITOR` (falling back to `vi`).
+///
+/// Blocks until editor exits. Returns `None` if editor exited with non-zero status, which
+/// is treated as cancelled edit.
+///
+/// Terminal must be released before calling this function. It must not be called on async
+/// runtime thread, e.g. use `spawn_blocking`.
+pub fn edit(value: &str) -> Result<Option<String>> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    let mut editor_args = editor.split_whitespace();
+    let Some(program) = editor_args.next() else {
+        bail!("Editor command is empty");
+    };
+
+    let (temp_file, mut file) = TempFile::create()?;
+    file.write_all(value.as_bytes())
+        .and_then(|_| file.sync_all())
+        .context("Failed to write temporary file")?;
+    drop(file);
+
+    let status = Command::new(program)
+        .args(editor_args)
+        .arg(&temp_file.0)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{editor}'"))?;
+    if !status.success() {
+        return Ok(None);
+    }
+
+    let mut edited = fs::read_to_string(&temp_file.0).context("Failed to read temporary file")?;
+    // most editors add trailing newline on save
+    if edited.ends_with('\n') && !value.ends_with('\n') {
+        edited.pop();
+    }
+    Ok(Some(edited))
+}
+
+/// Temporary file, which is removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    /// Create new file with random name, readable only by current user, without following
+    /// symlinks or reusing files, which already exist.
+    fn create() -> Result<(Self, File)> {
+        let rng = SystemRandom::new();
+        for _ in 0..TEMP_FILE_ATTEMPTS {
+            let mut suffix = [0; 8];
+            rng.fill(&mut suffix)
+                .map_err(|_| anyhow!("Failed to generate temporary file name"))?;
+            let suffix = suffix
+                .iter()
+                .map(|x| format!("{x:02x}"))
+                .collect::<String>();
+            let path = env::temp_dir().join(format!("etcd-tui-{suffix}.txt"));
+
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            match options.open(&path) {
+                Ok(file) => return Ok((Self(path), file)),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err).context("Failed to create temporary file"),
+            }
+        }
+        bail!("Failed to create temporary file with unused name")
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -2,8 +2,11 @@ use std::panic;
 
 use anyhow::Result;
 use clap::Parser;
-use futures::TryFutureExt;
-use tokio::{pin, spawn, sync::mpsc::unbounded_channel};
+use tokio::{
+    spawn,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    task::{spawn_blocking, JoinHandle},
+};
 
 use crate::{
     app::App, cli::Cli, components::Component, events::Event, shared_state::SharedState, tui::Tui,
@@ -15,26 +18,47 @@ mod events;
 mod app;
 mod cli;
 mod components;
+mod external_editor;
+mod merge;
+mod permission;
 mod shared_state;
+mod snapshot;
 mod tui;
+mod txn;
 mod ui;
 mod utils;
+mod watch_record;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    let (event_tx, mut event_rx) = unbounded_channel();
+    let (event_tx, event_rx) = unbounded_channel();
     let shared_state = SharedState::new(cli, event_tx).await?;
     let mut app = App::new(shared_state.clone());
 
     let mut tui = Tui::new()?;
+    let result = run(&mut app, &mut tui, &shared_state, event_rx).await;
+
+    let exit_result = tui.exit();
+    // ephemeral keys would otherwise live until session lease expires, even if app failed
+    let revoke_result = shared_state.revoke_session_lease().await;
+    result?;
+    app.take_result()?;
+    exit_result?;
+    revoke_result
+}
+
+/// Run event loop until app quits, terminal is left in TUI mode.
+async fn run(
+    app: &mut App,
+    tui: &mut Tui,
+    shared_state: &SharedState,
+    mut event_rx: UnboundedReceiver<Event>,
+) -> Result<()> {
     tui.enter()?;
 
-    let event_handler = spawn(events::event_handler(shared_state.clone()))
-        .err_into()
-        .and_then(|x| async { x });
-    pin!(event_handler);
+    let mut event_handler = spawn(events::event_handler(shared_state.clone()));
 
     while !app.should_quit() {
         tui.terminal_mut().draw(|f| app.draw(f, f.size()))?;
@@ -44,15 +68,39 @@ async fn main() -> Result<()> {
                 event.unwrap_or_else(|| Event::Quit(Ok(())))
             },
             eh = &mut event_handler => {
-                Event::Quit(eh)
+                Event::Quit(eh.map_err(Into::into).and_then(|x| x))
             }
         };
 
+        let event = match event {
+            Event::EditExternally(value) => {
+                // event handler reads terminal input, so it must be stopped while editor
+                // is running
+                stop_event_handler(&mut event_handler).await;
+                tui.exit()?;
+                // editor blocks until it exits, which must not stall other tasks, e.g.
+                // keep-alive of session lease
+                let result = spawn_blocking(move || external_editor::edit(&value))
+                    .await
+                    .map_err(Into::into)
+                    .and_then(|x| x);
+                tui.enter()?;
+                event_handler = spawn(events::event_handler(shared_state.clone()));
+                Event::ExternalEditDone(result)
+            }
+            rest => rest,
+        };
+
         if let Err(err) = app.handle_event(event) {
             app.quit(Err(err));
         }
     }
 
-    tui.exit()?;
-    app.take_result()
+    stop_event_handler(&mut event_handler).await;
+    Ok(())
+}
+
+async fn stop_event_handler(event_handler: &mut JoinHandle<Result<()>>) {
+    event_handler.abort();
+    let _ = event_handler.await;
 }
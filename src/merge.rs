@@ -0,0 +1,130 @@
+use similar::{DiffTag, TextDiff};
+
+pub const CONFLICT_START_MARKER: &str = "<<<<<<< local";
+
+/// Line-based three-way merge of `local` and `remote` changes made to `base`.
+///
+/// Conflicting hunks are written with git-style conflict markers.
+pub fn merge3(base: &str, local: &str, remote: &str) -> String {
+    let base_lines = base.split('\n').collect::<Vec<_>>();
+    let local_lines = local.split('\n').collect::<Vec<_>>();
+    let remote_lines = remote.split('\n').collect::<Vec<_>>();
+
+    let local_matches = matching_lines(&base_lines, &local_lines);
+    let remote_matches = matching_lines(&base_lines, &remote_lines);
+
+    let mut output: Vec<&str> = vec![];
+    let (mut base_idx, mut local_idx, mut remote_idx) = (0, 0, 0);
+
+    loop {
+        // next base line, which is unchanged on both sides
+        let stable = (base_idx..base_lines.len())
+            .find(|&x| local_matches[x].is_some() && remote_matches[x].is_some());
+        let (base_end, local_end, remote_end) = match stable {
+            Some(x) => (x, local_matches[x].unwrap(), remote_matches[x].unwrap()),
+            None => (base_lines.len(), local_lines.len(), remote_lines.len()),
+        };
+
+        let base_chunk = &base_lines[base_idx..base_end];
+        let local_chunk = &local_lines[local_idx..local_end];
+        let remote_chunk = &remote_lines[remote_idx..remote_end];
+
+        if local_chunk == base_chunk || local_chunk == remote_chunk {
+            output.extend(remote_chunk);
+        } else if remote_chunk == base_chunk {
+            output.extend(local_chunk);
+        } else {
+            output.push(CONFLICT_START_MARKER);
+            output.extend(local_chunk);
+            output.push("||||||| original");
+            output.extend(base_chunk);
+            output.push("=======");
+            output.extend(remote_chunk);
+            output.push(">>>>>>> remote");
+        }
+
+        let Some(stable) = stable else {
+            break;
+        };
+        output.push(base_lines[stable]);
+        base_idx = stable + 1;
+        local_idx = local_end + 1;
+        remote_idx = remote_end + 1;
+    }
+
+    output.join("\n")
+}
+
+/// For each line of `base` find index of equal line in `other`, if line is unchanged.
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for op in TextDiff::from_slices(base, other).ops() {
+        let (tag, base_range, other_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            for (base_idx, other_idx) in base_range.zip(other_range) {
+                matches[base_idx] = Some(other_idx);
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_changes_of_different_lines() {
+        let base = "a\nb\nc\nd";
+        let local = "A\nb\nc\nd";
+        let remote = "a\nb\nc\nD";
+        assert_eq!(merge3(base, local, remote), "A\nb\nc\nD");
+    }
+
+    #[test]
+    fn merges_insertions_and_deletions() {
+        let base = "a\nb\nc\nd\ne";
+        let local = "a\nx\nb\nc\nd\ne";
+        let remote = "a\nb\nc\ne";
+        assert_eq!(merge3(base, local, remote), "a\nx\nb\nc\ne");
+    }
+
+    #[test]
+    fn marks_conflicting_hunk() {
+        let base = "a\nb\nc";
+        let local = "a\nlocal\nc";
+        let remote = "a\nremote\nc";
+        assert_eq!(
+            merge3(base, local, remote),
+            "a\n<<<<<<< local\nlocal\n||||||| original\nb\n=======\nremote\n>>>>>>> remote\nc"
+        );
+    }
+
+    #[test]
+    fn takes_identical_change_once() {
+        let base = "a\nb\nc";
+        let changed = "a\nB\nc\nd";
+        assert_eq!(merge3(base, changed, changed), changed);
+    }
+
+    #[test]
+    fn keeps_unchanged_value() {
+        let base = "a\nb";
+        assert_eq!(merge3(base, base, base), base);
+        assert_eq!(merge3("", "", ""), "");
+    }
+
+    #[test]
+    fn merges_trailing_newline_difference() {
+        let base = "a\nb";
+        assert_eq!(merge3(base, "a\nb\n", base), "a\nb\n");
+        assert_eq!(merge3(base, base, "a\nb\n"), "a\nb\n");
+        assert_eq!(merge3(base, "A\nb", "a\nb\n"), "A\nb\n");
+    }
+
+    #[test]
+    fn merges_into_empty_base() {
+        assert_eq!(merge3("", "a", ""), "a");
+        assert!(merge3("", "a", "b").starts_with(CONFLICT_START_MARKER));
+    }
+}
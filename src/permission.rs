@@ -0,0 +1,465 @@
+use std::{cmp::max, fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::shared_state::Role;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PermissionKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Keys, which permission applies to. Keys are kept as bytes, as etcd doesn't require them
+/// to be valid UTF-8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyRange {
+    Key(Vec<u8>),
+    Prefix(Vec<u8>),
+    /// Keys from start (inclusive) to end (exclusive).
+    Range(Vec<u8>, Vec<u8>),
+    /// Keys greater than or equal to given one.
+    FromKey(Vec<u8>),
+    All,
+}
+
+/// Permission of role in `etcdctl`-like syntax, e.g. `readwrite prefix /app/`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permission {
+    pub kind: PermissionKind,
+    pub range: KeyRange,
+}
+
+/// Whether operation is allowed on key range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    Denied,
+    /// Allowed only for some keys of range.
+    Partial,
+    Allowed,
+}
+
+/// Permissions of current user, merged from all roles granted to it.
+#[derive(Clone, Debug)]
+pub struct EffectivePermissions {
+    pub user: Option<String>,
+    /// Reason, why all operations are allowed, e.g. user has root role.
+    pub unrestricted: Option<String>,
+    pub roles: Vec<Role>,
+}
+
+impl PermissionKind {
+    pub fn can_read(&self) -> bool {
+        matches!(self, Self::Read | Self::ReadWrite)
+    }
+
+    pub fn can_write(&self) -> bool {
+        matches!(self, Self::Write | Self::ReadWrite)
+    }
+}
+
+impl KeyRange {
+    /// Start key and range end, as used by etcd.
+    pub fn to_bytes(&self) -> (Vec<u8>, Vec<u8>) {
+        match self {
+            Self::Key(x) => (x.clone(), vec![]),
+            Self::Prefix(x) if x.is_empty() => Self::All.to_bytes(),
+            Self::Prefix(x) => (x.clone(), prefix_end(x)),
+            Self::Range(start, end) => (start.clone(), end.clone()),
+            Self::FromKey(x) => (x.clone(), vec![0]),
+            Self::All => (vec![0], vec![0]),
+        }
+    }
+
+    pub fn from_bytes(key: &[u8], range_end: &[u8]) -> Self {
+        match range_end {
+            [] => Self::Key(key.to_vec()),
+            [0] if key.is_empty() || key == [0] => Self::All,
+            [0] => Self::FromKey(key.to_vec()),
+            x if x == prefix_end(key) => Self::Prefix(key.to_vec()),
+            x => Self::Range(key.to_vec(), x.to_vec()),
+        }
+    }
+
+    /// Whether keys are valid UTF-8, so that range can be written as text and parsed back.
+    pub fn is_utf8(&self) -> bool {
+        let keys: &[&[u8]] = match self {
+            Self::Key(x) | Self::Prefix(x) | Self::FromKey(x) => &[x],
+            Self::Range(start, end) => &[start, end],
+            Self::All => &[],
+        };
+        keys.iter().all(|x| std::str::from_utf8(x).is_ok())
+    }
+
+    /// Start key (inclusive) and end key (exclusive), `None` if range has no end.
+    fn bounds(&self) -> (Vec<u8>, Option<Vec<u8>>) {
+        match self {
+            Self::Key(x) => {
+                let mut end = x.clone();
+                end.push(0);
+                (x.clone(), Some(end))
+            }
+            Self::Prefix(x) => match prefix_end(x) {
+                end if x.is_empty() || end == [0] => (x.clone(), None),
+                end => (x.clone(), Some(end)),
+            },
+            Self::Range(start, end) => (start.clone(), Some(end.clone())),
+            Self::FromKey(x) => (x.clone(), None),
+            Self::All => (vec![], None),
+        }
+    }
+
+    /// Whether all keys of `other` are in this range.
+    pub fn covers(&self, other: &KeyRange) -> bool {
+        let ((start, end), (other_start, other_end)) = (self.bounds(), other.bounds());
+        start <= other_start
+            && match (end, other_end) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(end), Some(other_end)) => other_end <= end,
+            }
+    }
+
+    /// Whether some keys of `other` are in this range.
+    pub fn overlaps(&self, other: &KeyRange) -> bool {
+        let ((start, end), (other_start, other_end)) = (self.bounds(), other.bounds());
+        end.is_none_or(|x| other_start < x) && other_end.is_none_or(|x| start < x)
+    }
+}
+
+impl EffectivePermissions {
+    pub fn unrestricted(user: Option<String>, reason: impl ToString) -> Self {
+        Self {
+            user,
+            unrestricted: Some(reason.to_string()),
+            roles: vec![],
+        }
+    }
+
+    /// Permissions of all roles, along with name of role granting them.
+    pub fn permissions(&self) -> impl Iterator<Item = (&str, &Permission)> {
+        self.roles.iter().flat_map(|role| {
+            role.permissions
+                .iter()
+                .map(move |x| (role.name.as_str(), x))
+        })
+    }
+
+    pub fn read_access(&self, range: &KeyRange) -> Access {
+        self.access(range, PermissionKind::can_read)
+    }
+
+    pub fn write_access(&self, range: &KeyRange) -> Access {
+        self.access(range, PermissionKind::can_write)
+    }
+
+    /// Whether `key` can be read, but not written.
+    pub fn is_read_only(&self, key: &str) -> bool {
+        let range = KeyRange::Key(key.as_bytes().to_vec());
+        self.read_access(&range) == Access::Allowed && self.write_access(&range) != Access::Allowed
+    }
+
+    /// Note, that range covered only by several permissions together is reported as partial.
+    fn access(&self, range: &KeyRange, is_allowed: fn(&PermissionKind) -> bool) -> Access {
+        if self.unrestricted.is_some() {
+            return Access::Allowed;
+        }
+        self.permissions()
+            .filter(|(_, x)| is_allowed(&x.kind))
+            .map(|(_, x)| {
+                if x.range.covers(range) {
+                    Access::Allowed
+                } else if x.range.overlaps(range) {
+                    Access::Partial
+                } else {
+                    Access::Denied
+                }
+            })
+            .fold(Access::Denied, max)
+    }
+}
+
+impl fmt::Display for PermissionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let x = match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::ReadWrite => "readwrite",
+        };
+        write!(f, "{x}")
+    }
+}
+
+/// Keys, which aren't valid UTF-8, are written lossily.
+impl fmt::Display for KeyRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |x: &[u8]| String::from_utf8_lossy(x).into_owned();
+        match self {
+            Self::Key(x) => write!(f, "key {}", text(x)),
+            Self::Prefix(x) => write!(f, "prefix {}", text(x)),
+            Self::Range(start, end) => write!(f, "range {} {}", text(start), text(end)),
+            Self::FromKey(x) => write!(f, "from {}", text(x)),
+            Self::All => write!(f, "all"),
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.range)
+    }
+}
+
+/// Parses e.g. `read key /a`, `readwrite prefix /app/`, `write range /a /c`, `read from /a`
+/// or `read all`.
+impl FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (kind, rest) = s.split_once(' ').unwrap_or((s, ""));
+        let kind = match kind {
+            "read" | "r" => PermissionKind::Read,
+            "write" | "w" => PermissionKind::Write,
+            "readwrite" | "rw" => PermissionKind::ReadWrite,
+            x => bail!("Unknown permission '{x}', expected read, write or readwrite"),
+        };
+
+        let range = rest.parse()?;
+        Ok(Self { kind, range })
+    }
+}
+
+/// Parses e.g. `key /a`, `prefix /app/`, `range /a /c`, `from /a` or `all`.
+impl FromStr for KeyRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (range_kind, key) = s.split_once(' ').unwrap_or((s, ""));
+        let key = key.trim();
+        let require_key = || {
+            if key.is_empty() {
+                Err(anyhow!("Missing key after '{range_kind}'"))
+            } else {
+                Ok(key.as_bytes().to_vec())
+            }
+        };
+        let range = match range_kind {
+            "key" => Self::Key(require_key()?),
+            "prefix" => Self::Prefix(require_key()?),
+            "from" => Self::FromKey(require_key()?),
+            "all" if key.is_empty() => Self::All,
+            "all" => bail!("Unexpected '{key}' after 'all'"),
+            "range" => {
+                let mut keys = key.split_whitespace();
+                match (keys.next(), keys.next(), keys.next()) {
+                    (Some(start), Some(end), None) => {
+                        Self::Range(start.as_bytes().to_vec(), end.as_bytes().to_vec())
+                    }
+                    _ => bail!("Expected start and end key after 'range'"),
+                }
+            }
+            x => bail!("Unknown range '{x}', expected key, prefix, range, from or all"),
+        };
+        Ok(range)
+    }
+}
+
+/// Smallest key greater than all keys with `prefix`, as computed by etcd.
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    match prefix.iter().rposition(|x| *x < 0xff) {
+        Some(idx) => {
+            let mut end = prefix[..=idx].to_vec();
+            end[idx] += 1;
+            end
+        }
+        // all keys, if prefix consists only of 0xff bytes
+        None => vec![0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(x: &str) -> KeyRange {
+        KeyRange::Key(x.into())
+    }
+
+    fn prefix(x: &str) -> KeyRange {
+        KeyRange::Prefix(x.into())
+    }
+
+    #[test]
+    fn computes_prefix_end() {
+        assert_eq!(prefix_end(b"/app/"), b"/app0");
+        assert_eq!(prefix_end(b"a\xff\xff"), b"b");
+        assert_eq!(prefix_end(b"\xff\xff"), [0]);
+        assert_eq!(prefix_end(b""), [0]);
+    }
+
+    #[test]
+    fn converts_ranges_from_bytes() {
+        assert_eq!(KeyRange::from_bytes(b"/a", b""), key("/a"));
+        assert_eq!(KeyRange::from_bytes(b"/app/", b"/app0"), prefix("/app/"));
+        assert_eq!(
+            KeyRange::from_bytes(b"/a", b"/c"),
+            KeyRange::Range("/a".into(), "/c".into())
+        );
+        assert_eq!(
+            KeyRange::from_bytes(b"/a", b"\0"),
+            KeyRange::FromKey("/a".into())
+        );
+        assert_eq!(KeyRange::from_bytes(b"\0", b"\0"), KeyRange::All);
+        assert_eq!(KeyRange::from_bytes(b"", b"\0"), KeyRange::All);
+    }
+
+    #[test]
+    fn round_trips_ranges_through_bytes() {
+        let ranges = [
+            key("/a"),
+            prefix("/app/"),
+            prefix("a\u{ff}"),
+            KeyRange::Range("/a".into(), "/c".into()),
+            KeyRange::FromKey("/a".into()),
+            KeyRange::All,
+            KeyRange::Key(b"/\xff\xfe".to_vec()),
+            KeyRange::Prefix(b"/\xc3".to_vec()),
+        ];
+        for range in ranges {
+            let (start, end) = range.to_bytes();
+            assert_eq!(KeyRange::from_bytes(&start, &end), range);
+        }
+    }
+
+    #[test]
+    fn converts_empty_and_maximal_prefixes() {
+        assert_eq!(prefix("").to_bytes(), KeyRange::All.to_bytes());
+        // prefix of only 0xff bytes has no end, like etcd computes it
+        let (start, end) = KeyRange::Prefix(b"\xff".to_vec()).to_bytes();
+        assert_eq!((start.as_slice(), end.as_slice()), (&b"\xff"[..], &[0][..]));
+        assert!(KeyRange::Prefix(b"\xff".to_vec()).covers(&KeyRange::FromKey(b"\xff\xff".to_vec())));
+    }
+
+    #[test]
+    fn detects_invalid_utf8() {
+        assert!(prefix("/app/").is_utf8());
+        assert!(KeyRange::All.is_utf8());
+        assert!(!KeyRange::Key(b"/\xff".to_vec()).is_utf8());
+        assert!(!KeyRange::Range("/a".into(), b"/\xff".to_vec()).is_utf8());
+    }
+
+    #[test]
+    fn checks_coverage() {
+        assert!(prefix("/app/").covers(&key("/app/x")));
+        assert!(prefix("/app/").covers(&prefix("/app/x/")));
+        assert!(!prefix("/app/").covers(&key("/apq")));
+        assert!(!prefix("/app/").covers(&prefix("/ap")));
+        assert!(key("/a").covers(&key("/a")));
+        assert!(!key("/a").covers(&key("/a/b")));
+        assert!(KeyRange::All.covers(&prefix("")));
+        assert!(prefix("").covers(&KeyRange::All));
+        assert!(KeyRange::Range("/a".into(), "/c".into()).covers(&prefix("/b")));
+        assert!(!KeyRange::Range("/a".into(), "/c".into()).covers(&prefix("/c")));
+        assert!(!KeyRange::FromKey("/b".into()).covers(&prefix("/a")));
+        assert!(!key("/a").covers(&KeyRange::FromKey("/a".into())));
+    }
+
+    #[test]
+    fn checks_overlap() {
+        assert!(prefix("/").overlaps(&prefix("/app/")));
+        assert!(prefix("/app/").overlaps(&prefix("/")));
+        assert!(!prefix("/app/").overlaps(&prefix("/apq/")));
+        assert!(!KeyRange::Range("/a".into(), "/c".into()).overlaps(&key("/c")));
+        assert!(KeyRange::FromKey("/b".into()).overlaps(&prefix("/")));
+        assert!(!KeyRange::FromKey("/b".into()).overlaps(&prefix("/a")));
+        assert!(KeyRange::All.overlaps(&key("")));
+    }
+
+    #[test]
+    fn parses_permissions() {
+        let cases = [
+            (
+                "readwrite prefix /app/",
+                PermissionKind::ReadWrite,
+                prefix("/app/"),
+            ),
+            ("r key /a", PermissionKind::Read, key("/a")),
+            (
+                "write range /a /c",
+                PermissionKind::Write,
+                KeyRange::Range("/a".into(), "/c".into()),
+            ),
+            (
+                " read  from /a ",
+                PermissionKind::Read,
+                KeyRange::FromKey("/a".into()),
+            ),
+            ("rw all", PermissionKind::ReadWrite, KeyRange::All),
+        ];
+        for (input, kind, range) in cases {
+            assert_eq!(
+                input.parse::<Permission>().unwrap(),
+                Permission { kind, range }
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_permissions() {
+        for input in [
+            "",
+            "read",
+            "read key",
+            "read all /a",
+            "admin key /a",
+            "read keys /a",
+            "write range /a",
+            "write range /a /b /c",
+        ] {
+            assert!(input.parse::<Permission>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn round_trips_permissions_through_text() {
+        for input in [
+            "readwrite prefix /app/",
+            "read key /a",
+            "write range /a /c",
+            "read from /a",
+            "read all",
+        ] {
+            assert_eq!(input.parse::<Permission>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn evaluates_access() {
+        let permissions = EffectivePermissions {
+            user: Some("app".into()),
+            unrestricted: None,
+            roles: vec![Role {
+                name: "app".into(),
+                permissions: vec![
+                    "read prefix /".parse().unwrap(),
+                    "write prefix /app/".parse().unwrap(),
+                ],
+            }],
+        };
+        assert_eq!(permissions.read_access(&prefix("/app/")), Access::Allowed);
+        assert_eq!(permissions.write_access(&prefix("/app/")), Access::Allowed);
+        assert_eq!(permissions.write_access(&prefix("/")), Access::Partial);
+        assert_eq!(permissions.write_access(&key("/other")), Access::Denied);
+        assert_eq!(permissions.read_access(&key("other")), Access::Denied);
+        assert!(permissions.is_read_only("/other"));
+        assert!(!permissions.is_read_only("/app/x"));
+        assert!(!permissions.is_read_only("other"));
+
+        let root = EffectivePermissions::unrestricted(Some("root".into()), "root role");
+        assert_eq!(root.write_access(&KeyRange::All), Access::Allowed);
+        assert!(!root.is_read_only("/a"));
+    }
+}
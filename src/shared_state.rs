@@ -1,63 +1,1959 @@
-use anyhow::{bail, Result};
-use etcd_client::{Client, ConnectOptions, GetOptions};
-use tokio::sync::mpsc::UnboundedSender;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
 
-use crate::{cli::Cli, events::Event};
+use anyhow::{anyhow, bail, Result};
+use etcd_client::{
+    AlarmAction, AlarmOptions, AlarmType, Client, CompactionOptions, Compare, CompareOp,
+    ConnectOptions, EventType, GetOptions, LeaseKeepAliveStream, LeaseKeeper,
+    LeaseTimeToLiveOptions, LockOptions, MemberAddOptions, PermissionType, PutOptions,
+    RoleRevokePermissionOptions, SortOrder, SortTarget, Txn, TxnOp, TxnOpResponse, WatchOptions,
+};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    spawn,
+    sync::mpsc::UnboundedSender,
+    time::{interval, sleep, timeout, MissedTickBehavior},
+};
+
+use crate::{
+    cli::Cli,
+    events::Event,
+    permission::{EffectivePermissions, KeyRange, Permission, PermissionKind},
+    snapshot::{SnapshotWriter, CHECKSUM_LEN},
+    txn::{
+        CompareOperator, CompareTarget, Transaction, TxnCompare, TxnOpOutcome, TxnOperation,
+        TxnOutcome,
+    },
+};
+
+/// Value of key along with its metadata.
+#[derive(Clone, Debug)]
+pub struct KeyValue {
+    pub value: String,
+    pub create_revision: i64,
+    pub mod_revision: i64,
+    pub version: i64,
+    /// ID of lease attached to key, 0 if none.
+    pub lease: i64,
+}
+
+impl TryFrom<&etcd_client::KeyValue> for KeyValue {
+    type Error = anyhow::Error;
+
+    fn try_from(kv: &etcd_client::KeyValue) -> Result<Self> {
+        Ok(Self {
+            value: kv.value_str()?.to_string(),
+            create_revision: kv.create_revision(),
+            mod_revision: kv.mod_revision(),
+            version: kv.version(),
+            lease: kv.lease(),
+        })
+    }
+}
+
+impl KeyValue {
+    /// Convert `kv`, replacing invalid UTF-8 in its value, e.g. when data already written
+    /// must not be lost due to decoding error.
+    fn from_lossy(kv: &etcd_client::KeyValue) -> Self {
+        Self {
+            value: String::from_utf8_lossy(kv.value()).into_owned(),
+            create_revision: kv.create_revision(),
+            mod_revision: kv.mod_revision(),
+            version: kv.version(),
+            lease: kv.lease(),
+        }
+    }
+
+    /// Whether it is a tombstone, i.e. revision, at which key was deleted.
+    pub fn is_deleted(&self) -> bool {
+        self.version == 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PutResult {
+    Done,
+    /// Key was modified after expected revision. Contains current value of key, `None` if key
+    /// was deleted.
+    Conflict(Option<KeyValue>),
+}
+
+/// Change, queued in staging mode to be committed along with others.
+#[derive(Clone, Debug)]
+pub enum StagedChange {
+    Put {
+        key: String,
+        value: String,
+        /// `None` if key didn't exist.
+        old_value: Option<String>,
+        /// Revision of key, when it was loaded. 0 if key didn't exist.
+        mod_revision: i64,
+        lease: PutLease,
+    },
+    Delete {
+        key: String,
+        old_value: String,
+        mod_revision: i64,
+    },
+    /// Put value of key `from` to key `to`, which must not exist, and delete `from`.
+    Rename {
+        from: String,
+        to: String,
+        value: String,
+        mod_revision: i64,
+        /// ID of lease, which is kept for renamed key, 0 if none.
+        lease: i64,
+    },
+}
+
+impl StagedChange {
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Self::Put { key, .. } | Self::Delete { key, .. } => vec![key],
+            Self::Rename { from, to, .. } => vec![from, to],
+        }
+    }
+
+    /// Keys along with revisions, they must still have, for change to be committed.
+    fn guards(&self) -> Vec<(String, i64)> {
+        match self {
+            Self::Put {
+                key, mod_revision, ..
+            }
+            | Self::Delete {
+                key, mod_revision, ..
+            } => vec![(key.clone(), *mod_revision)],
+            Self::Rename {
+                from,
+                to,
+                mod_revision,
+                ..
+            } => vec![(from.clone(), *mod_revision), (to.clone(), 0)],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CommitResult {
+    Done {
+        revision: i64,
+    },
+    /// Keys, which were modified since changes were staged. Nothing was committed.
+    Conflict(Vec<String>),
+}
+
+#[derive(Default)]
+struct Staging {
+    is_enabled: bool,
+    changes: Vec<StagedChange>,
+}
+
+/// Lease to put key with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PutLease {
+    /// Put key without lease, detaching current one.
+    #[default]
+    None,
+    /// Keep lease, currently attached to key.
+    Keep,
+    /// Attach existing lease with given ID.
+    Existing(i64),
+    /// Grant new lease with given TTL in seconds.
+    Grant(i64),
+}
+
+impl fmt::Display for PutLease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "no lease"),
+            Self::Keep => write!(f, "current lease"),
+            Self::Existing(id) => write!(f, "lease {id:#x}"),
+            Self::Grant(ttl) => write!(f, "new lease with TTL {ttl}s"),
+        }
+    }
+}
+
+/// Parses `none`, `keep`, TTL with unit (e.g. `30s` or `5m`) or lease ID in hex with `0x`
+/// prefix (e.g. `0x694d77aa9e38260f`).
+///
+/// Prefix and unit are required, as e.g. `12d` would be both valid TTL and lease ID.
+impl FromStr for PutLease {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s {
+            "none" => return Ok(Self::None),
+            "keep" => return Ok(Self::Keep),
+            _ => {}
+        }
+        if let Some(id) = s.strip_prefix("0x") {
+            return match i64::from_str_radix(id, 16) {
+                Ok(x) if x > 0 => Ok(Self::Existing(x)),
+                _ => Err(anyhow!("Invalid lease ID '{s}'")),
+            };
+        }
+        if s.chars().all(|x| x.is_ascii_digit()) {
+            bail!("TTL '{s}' needs unit, e.g. '{s}s', and lease ID needs prefix, e.g. '0x{s}'");
+        }
+        match humantime::parse_duration(s) {
+            Ok(ttl) if ttl.as_secs() == 0 => Err(anyhow!("TTL must be at least 1 second")),
+            Ok(ttl) => Ok(Self::Grant(ttl.as_secs() as i64)),
+            Err(_) => Err(anyhow!("Invalid lease '{s}'")),
+        }
+    }
+}
+
+/// Past values of key, newest first. Deletions of key are included as revisions, which
+/// are deleted.
+#[derive(Clone, Debug)]
+pub struct KeyHistory {
+    pub revisions: Vec<KeyValue>,
+    /// Whether older revisions are unavailable due to compaction.
+    pub is_compacted: bool,
+    /// Whether older revisions were omitted, as there are too many of them.
+    pub is_truncated: bool,
+}
+
+/// Difference in value of single key between two revisions.
+#[derive(Clone, Debug)]
+pub struct KeyChange {
+    pub key: String,
+    /// `None` if key was added.
+    pub old_value: Option<String>,
+    /// `None` if key was removed.
+    pub new_value: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WatchEventKind {
+    Put,
+    Delete,
+}
+
+/// Single change of key, received from watch.
+#[derive(Clone, Debug)]
+pub struct WatchEvent {
+    pub revision: i64,
+    pub kind: WatchEventKind,
+    pub key: String,
+    /// `None` for deletions.
+    pub value: Option<String>,
+    /// `None` if key didn't exist before.
+    pub prev_value: Option<String>,
+    /// Time, when event was received.
+    pub time: SystemTime,
+    /// Whether key or values weren't valid UTF-8 and were decoded lossily.
+    pub is_lossy: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum WatchUpdate {
+    Event(WatchEvent),
+    /// Watch failed and will be restarted from last seen revision.
+    Disconnected(String),
+}
+
+/// Lease along with keys attached to it.
+#[derive(Clone, Debug)]
+pub struct Lease {
+    pub id: i64,
+    /// TTL in seconds, lease was granted or last renewed with.
+    pub granted_ttl: i64,
+    /// Remaining TTL in seconds, at the time lease was loaded.
+    pub ttl: i64,
+    pub keys: Vec<String>,
+}
+
+/// Member of cluster along with status of its endpoint.
+#[derive(Clone, Debug)]
+pub struct MemberStatus {
+    pub id: u64,
+    pub name: String,
+    pub peer_urls: Vec<String>,
+    pub client_urls: Vec<String>,
+    pub is_learner: bool,
+    /// Error message, if status couldn't be loaded.
+    pub status: Result<EndpointStatus, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EndpointStatus {
+    pub version: String,
+    /// Latest revision of keyspace, seen by member.
+    pub revision: i64,
+    /// Physically allocated size of database in bytes.
+    pub db_size: i64,
+    /// Logically used size of database in bytes.
+    pub db_size_in_use: i64,
+    /// ID of member, which this one considers a leader.
+    pub leader: u64,
+    pub raft_term: u64,
+    pub raft_index: u64,
+    pub raft_applied_index: u64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlarmKind {
+    /// Space quota is exhausted, cluster accepts only reads and deletes.
+    NoSpace,
+    /// Corruption of member's data was detected.
+    Corrupt,
+}
+
+/// Alarm, raised by member of cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alarm {
+    pub member_id: u64,
+    pub kind: AlarmKind,
+}
+
+impl fmt::Display for AlarmKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSpace => write!(f, "NOSPACE"),
+            Self::Corrupt => write!(f, "CORRUPT"),
+        }
+    }
+}
+
+impl fmt::Display for Alarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on member {:x}", self.kind, self.member_id)
+    }
+}
+
+impl From<AlarmKind> for AlarmType {
+    fn from(value: AlarmKind) -> Self {
+        match value {
+            AlarmKind::NoSpace => Self::Nospace,
+            AlarmKind::Corrupt => Self::Corrupt,
+        }
+    }
+}
+
+/// Hash of keyspace of single member.
+#[derive(Copy, Clone, Debug)]
+pub struct KvHash {
+    pub hash: u32,
+    /// Revision, keyspace was compacted at. Only hashes with same one are comparable.
+    pub compact_revision: i64,
+}
+
+/// State of single member, compared with other ones.
+#[derive(Clone, Debug)]
+pub struct MemberConsistency {
+    pub id: u64,
+    pub name: String,
+    /// Error message, if hash couldn't be computed.
+    pub hash: Result<KvHash, String>,
+    /// Checked key as read from member, `None` inside if it doesn't exist there.
+    pub key_value: Option<Result<Option<KeyValue>, String>>,
+}
+
+/// Result of comparing members of cluster at the same revision.
+#[derive(Clone, Debug)]
+pub struct ConsistencyReport {
+    pub revision: i64,
+    /// Key, which was read from each member, if any.
+    pub key: Option<String>,
+    pub members: Vec<MemberConsistency>,
+}
+
+impl ConsistencyReport {
+    /// Whether members with same compact revision have different hashes.
+    pub fn has_hash_mismatch(&self) -> bool {
+        let hashes = self
+            .members
+            .iter()
+            .filter_map(|x| x.hash.as_ref().ok())
+            .collect::<Vec<_>>();
+        hashes.iter().any(|x| {
+            hashes
+                .iter()
+                .any(|y| x.compact_revision == y.compact_revision && x.hash != y.hash)
+        })
+    }
+
+    /// Whether members returned different value or revision of checked key.
+    pub fn has_key_mismatch(&self) -> bool {
+        let values = self
+            .members
+            .iter()
+            .filter_map(|x| x.key_value.as_ref()?.as_ref().ok())
+            .map(|x| x.as_ref().map(|x| (&x.value, x.mod_revision)))
+            .collect::<Vec<_>>();
+        values.windows(2).any(|x| x[0] != x[1])
+    }
+}
+
+/// User of etcd auth along with roles granted to it.
+#[derive(Clone, Debug)]
+pub struct User {
+    pub name: String,
+    pub roles: Vec<String>,
+}
+
+/// Role of etcd auth along with key ranges it can access.
+#[derive(Clone, Debug)]
+pub struct Role {
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// Key, which etcd lock API creates for client waiting for lock. Waiter with lowest create
+/// revision holds the lock.
+#[derive(Clone, Debug)]
+pub struct LockWaiter {
+    pub key: String,
+    pub create_revision: i64,
+    /// ID of lease attached to key, 0 if none.
+    pub lease: i64,
+    /// Remaining TTL of lease in seconds, `None` if there is no lease or it has expired.
+    pub ttl: Option<i64>,
+}
+
+/// Timeout of requests to single member.
+const MEMBER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Time to wait for transfer of leadership to be observed.
+const LEADER_TRANSFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of operations or compares in single transaction, as etcd allows by default
+/// (`--max-txn-ops`).
+const MAX_TXN_OPS: usize = 128;
+
+/// Maximum number of revisions kept in history of key, older ones are omitted.
+const MAX_KEY_HISTORY: usize = 1000;
+
+/// Interval of asking etcd, whether watch has caught up with past events.
+const WATCH_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// TTL in seconds of lease, which ephemeral keys are attached to.
+const SESSION_LEASE_TTL: i64 = 10;
+
+/// Lease, kept alive while TUI runs, along with ephemeral keys attached to it.
+#[derive(Default)]
+struct Session {
+    lease_id: Option<i64>,
+    keys: Vec<String>,
+}
 
 #[derive(Clone)]
 pub struct SharedState {
-    etcd_client: Client,
+    /// Client, which is replaced when reconnecting, e.g. after auth was enabled.
+    etcd_client: Arc<Mutex<Client>>,
     event_tx: UnboundedSender<Event>,
+    cli: Arc<Cli>,
+    /// User name and password, which client is authenticated with.
+    credentials: Arc<Mutex<Option<(String, String)>>>,
+    /// Revision, at which keys are read in time-travel mode.
+    historical_revision: Arc<Mutex<Option<i64>>>,
+    session: Arc<Mutex<Session>>,
+    staging: Arc<Mutex<Staging>>,
+    /// Clients, connected to single member, by its client URLs.
+    member_clients: Arc<Mutex<HashMap<Vec<String>, Client>>>,
+    /// Active alarms, as of last time they were loaded.
+    alarms: Arc<Mutex<Vec<Alarm>>>,
 }
 
 impl SharedState {
     pub async fn new(cli: Cli, event_tx: UnboundedSender<Event>) -> Result<Self> {
-        let mut client_conn_opts = ConnectOptions::new();
-        if let Some((user, password)) = cli.credentials() {
-            client_conn_opts = client_conn_opts.with_user(user, password);
-        }
+        let credentials = cli.credentials();
+        let client_conn_opts = connect_options(&cli, credentials.clone());
         Ok(Self {
-            etcd_client: Client::connect(cli.endpoints, Some(client_conn_opts)).await?,
+            etcd_client: Arc::new(Mutex::new(
+                Client::connect(&cli.endpoints, Some(client_conn_opts)).await?,
+            )),
             event_tx,
+            cli: Arc::new(cli),
+            credentials: Arc::new(Mutex::new(credentials)),
+            historical_revision: Arc::new(Mutex::new(None)),
+            session: Arc::new(Mutex::new(Session::default())),
+            staging: Arc::new(Mutex::new(Staging::default())),
+            member_clients: Arc::new(Mutex::new(HashMap::new())),
+            alarms: Arc::new(Mutex::new(vec![])),
+        })
+    }
+
+    /// Client, connected only to member with `client_urls`, so that requests are served by it.
+    pub async fn member_client(&self, client_urls: &[String]) -> Result<Client> {
+        if client_urls.is_empty() {
+            bail!("Member has not started yet");
+        }
+        let cached = self
+            .member_clients
+            .lock()
+            .expect("Lock not poisoned")
+            .get(client_urls)
+            .cloned();
+        if let Some(x) = cached {
+            return Ok(x);
+        }
+
+        // no request timeout, since e.g. defragmentation may take long
+        let options =
+            connect_options(&self.cli, self.credentials()).with_connect_timeout(MEMBER_TIMEOUT);
+        let client = Client::connect(client_urls, Some(options)).await?;
+        self.member_clients
+            .lock()
+            .expect("Lock not poisoned")
+            .insert(client_urls.to_vec(), client.clone());
+        Ok(client)
+    }
+
+    /// List members of cluster along with status of each one.
+    pub async fn load_cluster_status(&self) -> Result<Vec<MemberStatus>> {
+        let response = self.etcd_client().member_list().await?;
+        let statuses = join_all(response.members().iter().map(|x| async move {
+            let status = self
+                .load_member_status(x.client_urls())
+                .await
+                .map_err(|err| err.to_string());
+            MemberStatus {
+                id: x.id(),
+                name: x.name().to_string(),
+                peer_urls: x.peer_urls().to_vec(),
+                client_urls: x.client_urls().to_vec(),
+                is_learner: x.is_learner(),
+                status,
+            }
+        }))
+        .await;
+        Ok(statuses)
+    }
+
+    async fn load_member_status(&self, client_urls: &[String]) -> Result<EndpointStatus> {
+        let mut client = self.member_client(client_urls).await?;
+        let response = timeout(MEMBER_TIMEOUT, client.status()).await??;
+        Ok(EndpointStatus {
+            version: response.version().to_string(),
+            revision: response.header().map_or(0, |x| x.revision()),
+            db_size: response.db_size(),
+            db_size_in_use: response.raft_used_db_size(),
+            leader: response.leader(),
+            raft_term: response.raft_term(),
+            raft_index: response.raft_index(),
+            raft_applied_index: response.raft_applied_index(),
+            errors: response.errors().to_vec(),
+        })
+    }
+
+    /// Add member with `peer_urls` and return its ID. Member has to be started afterwards
+    /// with `--initial-cluster-state=existing`.
+    pub async fn add_member(&self, peer_urls: &[String], is_learner: bool) -> Result<u64> {
+        let options = is_learner.then(|| MemberAddOptions::new().with_is_learner());
+        let response = self.etcd_client().member_add(peer_urls, options).await?;
+        let member = response
+            .member()
+            .ok_or_else(|| anyhow!("Added member is missing in response"))?;
+        Ok(member.id())
+    }
+
+    pub async fn remove_member(&self, id: u64) -> Result<()> {
+        self.etcd_client().member_remove(id).await?;
+        Ok(())
+    }
+
+    pub async fn update_member_peer_urls(&self, id: u64, peer_urls: &[String]) -> Result<()> {
+        self.etcd_client()
+            .member_update(id, peer_urls.to_vec())
+            .await?;
+        Ok(())
+    }
+
+    /// Promote learner to voting member, fails unless learner caught up with leader.
+    pub async fn promote_member(&self, id: u64) -> Result<()> {
+        self.etcd_client().member_promote(id).await?;
+        Ok(())
+    }
+
+    /// Compute hash of keyspace of each member at the same revision, which all reachable
+    /// members have, and read `key`, if any, from each member individually.
+    pub async fn check_consistency(&self, key: Option<&str>) -> Result<ConsistencyReport> {
+        let members = self.load_cluster_status().await?;
+        let revision = members
+            .iter()
+            .filter_map(|x| x.status.as_ref().ok().map(|x| x.revision))
+            .min()
+            .ok_or_else(|| anyhow!("No member is reachable"))?;
+
+        let members = join_all(members.iter().map(|x| async move {
+            let hash = async {
+                let mut client = self.member_client(&x.client_urls).await?;
+                let response = timeout(MEMBER_TIMEOUT, client.hash_kv(revision)).await??;
+                anyhow::Ok(KvHash {
+                    hash: response.hash(),
+                    compact_revision: response.compact_version(),
+                })
+            }
+            .await
+            .map_err(|err| err.to_string());
+            let key_value = match key {
+                Some(key) => Some(
+                    self.find_member_key(&x.client_urls, key, revision)
+                        .await
+                        .map_err(|err| err.to_string()),
+                ),
+                None => None,
+            };
+            MemberConsistency {
+                id: x.id,
+                name: x.name.clone(),
+                hash,
+                key_value,
+            }
+        }))
+        .await;
+
+        Ok(ConsistencyReport {
+            revision,
+            key: key.map(String::from),
+            members,
+        })
+    }
+
+    /// Get key at `revision`, as seen by member with `client_urls`, without consulting rest of
+    /// cluster.
+    async fn find_member_key(
+        &self,
+        client_urls: &[String],
+        key: &str,
+        revision: i64,
+    ) -> Result<Option<KeyValue>> {
+        // writes made during the check would otherwise differ between members
+        let options = GetOptions::new()
+            .with_serializable()
+            .with_revision(revision);
+        let response = timeout(
+            MEMBER_TIMEOUT,
+            self.member_client(client_urls)
+                .await?
+                .get(key, Some(options)),
+        )
+        .await??;
+        match response.kvs() {
+            [] => Ok(None),
+            [x] => Ok(Some(x.try_into()?)),
+            _ => bail!("Multiple key values returned"),
+        }
+    }
+
+    /// Transfer leadership to member `target_id` and wait until it observes itself as leader.
+    pub async fn move_leader(&self, target_id: u64) -> Result<()> {
+        let members = self.load_cluster_status().await?;
+        let leaders = members
+            .iter()
+            .filter_map(|x| x.status.as_ref().ok().map(|x| x.leader))
+            .collect::<Vec<_>>();
+        if leaders.is_empty() {
+            bail!("Leader is unknown, no member is reachable");
+        }
+        // members report leader 0, e.g. during election
+        let Some(leader) = leaders.into_iter().find(|x| *x != 0) else {
+            bail!("Cluster has no leader, leadership cannot be transferred until one is elected");
+        };
+        if leader == target_id {
+            bail!("Member is already leader");
+        }
+        let client_urls = |id| {
+            members
+                .iter()
+                .find(|x| x.id == id)
+                .map(|x| x.client_urls.clone())
+                .ok_or_else(|| anyhow!("Member {id:x} not found"))
+        };
+        let leader_urls = client_urls(leader)?;
+        let target_urls = client_urls(target_id)?;
+
+        // only leader can transfer leadership
+        let mut leader_client = self.member_client(&leader_urls).await?;
+        timeout(MEMBER_TIMEOUT, leader_client.move_leader(target_id))
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "Leader did not respond within {}s",
+                    MEMBER_TIMEOUT.as_secs()
+                )
+            })??;
+
+        timeout(LEADER_TRANSFER_TIMEOUT, async {
+            loop {
+                if let Ok(x) = self.load_member_status(&target_urls).await {
+                    if x.leader == target_id {
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(200)).await;
+            }
+        })
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Transfer of leadership was not observed within {}s",
+                LEADER_TRANSFER_TIMEOUT.as_secs()
+            )
+        })
+    }
+
+    /// Compact keyspace, discarding history before `revision`. If `physical`, wait until
+    /// compaction is physically applied to database.
+    pub async fn compact(&self, revision: i64, physical: bool) -> Result<()> {
+        let options = physical.then(|| CompactionOptions::new().with_physical());
+        self.etcd_client().compact(revision, options).await?;
+        Ok(())
+    }
+
+    /// Defragment database of member with `client_urls` and return its size in bytes before
+    /// and after defragmentation.
+    pub async fn defragment_member(&self, client_urls: &[String]) -> Result<(i64, i64)> {
+        let before = self.load_member_status(client_urls).await?.db_size;
+        self.member_client(client_urls).await?.defragment().await?;
+        let after = self.load_member_status(client_urls).await?.db_size;
+        Ok((before, after))
+    }
+
+    /// Stream snapshot of database from member with `client_urls` to new file at `path`,
+    /// adding number of received bytes to `progress`. Return size of file, after its size
+    /// and checksum were verified.
+    pub async fn save_snapshot(
+        &self,
+        client_urls: &[String],
+        path: &Path,
+        progress: Arc<AtomicU64>,
+    ) -> Result<u64> {
+        let mut stream = self.member_client(client_urls).await?.snapshot().await?;
+        let mut writer = SnapshotWriter::create(path).await?;
+        let mut expected_size = None;
+        while let Some(response) = stream.message().await? {
+            writer.write(response.blob()).await?;
+            let received = progress.fetch_add(response.blob().len() as u64, Ordering::Relaxed)
+                + response.blob().len() as u64;
+            // announced size doesn't include checksum, sent in last message
+            expected_size.get_or_insert(received + response.remaining_bytes() + CHECKSUM_LEN);
+        }
+        writer.finish(expected_size.unwrap_or(CHECKSUM_LEN)).await
+    }
+
+    /// Active alarms, as of last time they were loaded.
+    pub fn active_alarms(&self) -> Vec<Alarm> {
+        self.alarms.lock().expect("Lock not poisoned").clone()
+    }
+
+    pub async fn load_alarms(&self) -> Result<Vec<Alarm>> {
+        let response = self
+            .etcd_client()
+            .alarm(AlarmAction::Get, AlarmType::None, None)
+            .await?;
+        let alarms = response
+            .alarms()
+            .iter()
+            .filter_map(|x| {
+                let kind = match x.alarm() {
+                    AlarmType::Nospace => AlarmKind::NoSpace,
+                    AlarmType::Corrupt => AlarmKind::Corrupt,
+                    AlarmType::None => return None,
+                };
+                Some(Alarm {
+                    member_id: x.member_id(),
+                    kind,
+                })
+            })
+            .collect::<Vec<_>>();
+        *self.alarms.lock().expect("Lock not poisoned") = alarms.clone();
+        Ok(alarms)
+    }
+
+    pub async fn disarm_alarm(&self, alarm: &Alarm) -> Result<()> {
+        let mut options = AlarmOptions::new();
+        options.with_member(alarm.member_id);
+        self.etcd_client()
+            .alarm(AlarmAction::Deactivate, alarm.kind.into(), Some(options))
+            .await?;
+        self.load_alarms().await?;
+        Ok(())
+    }
+
+    /// Mention active alarms in error of rejected write, since they are likely its cause.
+    async fn explain_write_error(&self, err: etcd_client::Error) -> anyhow::Error {
+        match self.load_alarms().await {
+            Ok(alarms) if !alarms.is_empty() => {
+                let alarms = alarms.iter().map(Alarm::to_string).collect::<Vec<_>>();
+                anyhow!(
+                    "Rejected because of active alarm ({}): {err}",
+                    alarms.join(", ")
+                )
+            }
+            _ if is_permission_denied_error(&err) => anyhow!(
+                "{err}, effective permissions of {} can be inspected in key selection",
+                self.current_user().unwrap_or_else(|| "current user".into())
+            ),
+            _ => err.into(),
+        }
+    }
+
+    pub async fn load_users(&self) -> Result<Vec<User>> {
+        let response = self.etcd_client().user_list().await.map_err(auth_error)?;
+        let users = join_all(response.users().iter().map(|name| async move {
+            let response = self
+                .etcd_client()
+                .user_get(name.as_str())
+                .await
+                .map_err(auth_error)?;
+            anyhow::Ok(User {
+                name: name.clone(),
+                roles: response.roles().to_vec(),
+            })
+        }))
+        .await;
+        users.into_iter().collect()
+    }
+
+    pub async fn add_user(&self, name: &str, password: &str) -> Result<()> {
+        self.etcd_client()
+            .user_add(name, password, None)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn delete_user(&self, name: &str) -> Result<()> {
+        self.etcd_client()
+            .user_delete(name)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn change_password(&self, name: &str, password: &str) -> Result<()> {
+        self.etcd_client()
+            .user_change_password(name, password)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn grant_role(&self, user: &str, role: &str) -> Result<()> {
+        self.etcd_client()
+            .user_grant_role(user, role)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn revoke_role(&self, user: &str, role: &str) -> Result<()> {
+        self.etcd_client()
+            .user_revoke_role(user, role)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn load_role_names(&self) -> Result<Vec<String>> {
+        let response = self.etcd_client().role_list().await.map_err(auth_error)?;
+        Ok(response.roles().to_vec())
+    }
+
+    pub async fn load_roles(&self) -> Result<Vec<Role>> {
+        let names = self.load_role_names().await?;
+        self.load_roles_by_name(names).await
+    }
+
+    async fn load_roles_by_name(&self, names: Vec<String>) -> Result<Vec<Role>> {
+        let roles = join_all(names.into_iter().map(|name| async move {
+            let response = self
+                .etcd_client()
+                .role_get(name.as_str())
+                .await
+                .map_err(auth_error)?;
+            let permissions = response.permissions().iter().map(from_permission).collect();
+            anyhow::Ok(Role { name, permissions })
+        }))
+        .await;
+        roles.into_iter().collect()
+    }
+
+    fn credentials(&self) -> Option<(String, String)> {
+        self.credentials.lock().expect("Lock not poisoned").clone()
+    }
+
+    /// User, which is authenticated with etcd, `None` if connected without credentials.
+    pub fn current_user(&self) -> Option<String> {
+        self.credentials().map(|(user, _)| user)
+    }
+
+    /// Replace client with one authenticated with `credentials`, or unauthenticated one.
+    /// Current client is kept, if connecting fails.
+    async fn reconnect(&self, credentials: Option<(String, String)>) -> Result<()> {
+        let options = connect_options(&self.cli, credentials.clone());
+        let client = Client::connect(&self.cli.endpoints, Some(options)).await?;
+        *self.etcd_client.lock().expect("Lock not poisoned") = client;
+        *self.credentials.lock().expect("Lock not poisoned") = credentials;
+        self.member_clients
+            .lock()
+            .expect("Lock not poisoned")
+            .clear();
+        Ok(())
+    }
+
+    /// Check, that user 'root' with role 'root' exists, without which etcd refuses to enable
+    /// auth, since nobody could administer cluster afterwards.
+    pub async fn check_root_user(&self) -> Result<()> {
+        let has_root_role = match self.etcd_client().user_get("root").await {
+            Ok(response) => response.roles().iter().any(|x| x == "root"),
+            Err(etcd_client::Error::GRpcStatus(x))
+                if x.message().contains("user name not found") =>
+            {
+                bail!("User 'root' does not exist, add it before enabling auth")
+            }
+            Err(err) => return Err(auth_error(err)),
+        };
+        if !has_root_role {
+            bail!("Role 'root' is not granted to user 'root', grant it before enabling auth");
+        }
+        Ok(())
+    }
+
+    /// Enable auth and reconnect with `credentials`, or with current ones if `None`.
+    pub async fn enable_auth(&self, credentials: Option<(String, String)>) -> Result<()> {
+        let Some((user, password)) = credentials.or_else(|| self.credentials()) else {
+            bail!("Credentials are required to reconnect after enabling auth");
+        };
+        self.check_root_user().await?;
+        self.etcd_client().auth_enable().await.map_err(auth_error)?;
+        // current client is kept on failure, but it's rejected by etcd from now on
+        self.reconnect(Some((user.clone(), password)))
+            .await
+            .map_err(|err| {
+                anyhow!(
+                    "Auth was enabled, but reconnecting as '{user}' failed: {err}. Restart this \
+                     app with correct credentials, e.g. to disable auth again."
+                )
+            })
+    }
+
+    /// Disable auth and reconnect without credentials.
+    pub async fn disable_auth(&self) -> Result<()> {
+        self.etcd_client()
+            .auth_disable()
+            .await
+            .map_err(auth_error)?;
+        self.reconnect(None)
+            .await
+            .map_err(|err| anyhow!("Auth was disabled, but reconnecting failed: {err}"))
+    }
+
+    /// Permissions of current user. Users can read their own roles without being admins.
+    pub async fn load_effective_permissions(&self) -> Result<EffectivePermissions> {
+        let Some(user) = self.current_user() else {
+            return Ok(EffectivePermissions::unrestricted(
+                None,
+                "Connected without credentials, which works only while auth is disabled",
+            ));
+        };
+        if user == "root" {
+            return Ok(EffectivePermissions::unrestricted(
+                Some(user),
+                "User 'root' is always allowed everything",
+            ));
+        }
+
+        let response = self
+            .etcd_client()
+            .user_get(user.as_str())
+            .await
+            .map_err(auth_error)?;
+        let role_names = response.roles().to_vec();
+        if role_names.iter().any(|x| x == "root") {
+            return Ok(EffectivePermissions::unrestricted(
+                Some(user),
+                "Role 'root' allows everything",
+            ));
+        }
+        Ok(EffectivePermissions {
+            user: Some(user),
+            unrestricted: None,
+            roles: self.load_roles_by_name(role_names).await?,
         })
     }
 
+    pub async fn add_role(&self, name: &str) -> Result<()> {
+        self.etcd_client()
+            .role_add(name)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn delete_role(&self, name: &str) -> Result<()> {
+        self.etcd_client()
+            .role_delete(name)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn grant_permission(&self, role: &str, permission: &Permission) -> Result<()> {
+        self.etcd_client()
+            .role_grant_permission(role, to_permission(permission))
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    pub async fn revoke_permission(&self, role: &str, permission: &Permission) -> Result<()> {
+        let (key, range_end) = permission.range.to_bytes();
+        let options = (!range_end.is_empty())
+            .then(|| RoleRevokePermissionOptions::new().with_range_end(range_end));
+        self.etcd_client()
+            .role_revoke_permission(role, key, options)
+            .await
+            .map_err(auth_error)?;
+        Ok(())
+    }
+
+    /// Replace permission of role, new one is granted first, so that access is not lost
+    /// in between.
+    pub async fn replace_permission(
+        &self,
+        role: &str,
+        old: &Permission,
+        new: &Permission,
+    ) -> Result<()> {
+        self.grant_permission(role, new).await?;
+        if old.range != new.range {
+            self.revoke_permission(role, old).await?;
+        }
+        Ok(())
+    }
+
     pub fn etcd_client(&self) -> Client {
-        self.etcd_client.clone()
+        self.etcd_client.lock().expect("Lock not poisoned").clone()
+    }
+
+    pub fn cli(&self) -> &Cli {
+        &self.cli
+    }
+
+    /// Revision, at which keyspace is browsed in time-travel mode, `None` if not in this mode.
+    pub fn historical_revision(&self) -> Option<i64> {
+        *self.historical_revision.lock().expect("Lock not poisoned")
+    }
+
+    pub fn set_historical_revision(&self, revision: Option<i64>) {
+        *self.historical_revision.lock().expect("Lock not poisoned") = revision;
+    }
+
+    /// Revision to read keys at, 0 means latest.
+    fn read_revision(&self) -> i64 {
+        self.historical_revision().unwrap_or(0)
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if let Some(x) = self.historical_revision() {
+            bail!("Keyspace is read-only at historical revision {x}");
+        }
+        Ok(())
+    }
+
+    /// Check that keyspace can be read at `revision`, i.e. it is neither compacted nor from
+    /// the future.
+    pub async fn check_revision(&self, revision: i64) -> Result<()> {
+        let options = GetOptions::new()
+            .with_all_keys()
+            .with_count_only()
+            .with_revision(revision);
+        let _ = self.etcd_client().get(vec![], Some(options)).await?;
+        Ok(())
     }
 
     pub async fn load_keys(&self) -> Result<Vec<String>> {
         self.etcd_client()
             .get(
                 vec![],
-                Some(GetOptions::new().with_all_keys().with_keys_only()),
+                Some(
+                    GetOptions::new()
+                        .with_all_keys()
+                        .with_keys_only()
+                        .with_revision(self.read_revision()),
+                ),
             )
             .await?
             .kvs()
             .iter()
-            .map(|x| x.key_str().map(|x| x.to_string()))
-            .collect::<Result<_, _>>()
-            .map_err(Into::into)
+            .map(|x| Ok(x.key_str()?.to_string()))
+            .collect()
     }
 
-    pub async fn get_key(&self, key: &str) -> Result<String> {
-        let response = self.etcd_client().get(key, None).await?;
+    pub async fn get_key(&self, key: &str) -> Result<KeyValue> {
+        match self.find_key(key).await? {
+            Some(x) => Ok(x),
+            None => bail!("Key not found"),
+        }
+    }
 
-        match response.kvs().len() {
-            1 => {}
-            0 => bail!("Key not found"),
+    /// Get key, `None` if it doesn't exist.
+    pub async fn find_key(&self, key: &str) -> Result<Option<KeyValue>> {
+        let options = GetOptions::new().with_revision(self.read_revision());
+        let response = self.etcd_client().get(key, Some(options)).await?;
+
+        match response.kvs() {
+            [] => Ok(None),
+            [x] => Ok(Some(x.try_into()?)),
             _ => bail!("Multiple key values returned"),
         }
-        // SAFETY: just checked length of kvs()
-        Ok(response.kvs()[0].value_str()?.to_string())
     }
 
-    pub async fn put_key(&self, key: &str, value: String) -> Result<()> {
-        let _ = self.etcd_client().put(key, value, None).await?;
+    /// Put value with `lease`, if key wasn't modified since `mod_revision`.
+    ///
+    /// `mod_revision` of 0 means that key must not exist.
+    pub async fn put_key(
+        &self,
+        key: &str,
+        value: String,
+        mod_revision: i64,
+        lease: PutLease,
+    ) -> Result<PutResult> {
+        self.ensure_writable()?;
+        let mut client = self.etcd_client();
+        let (options, granted_lease) = put_options(&mut client, lease, mod_revision).await?;
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(
+                key,
+                CompareOp::Equal,
+                mod_revision,
+            )])
+            .and_then(vec![TxnOp::put(key, value, options)])
+            .or_else(vec![TxnOp::get(key, None)]);
+        let response = match client.txn(txn).await {
+            Ok(x) if x.succeeded() => return Ok(PutResult::Done),
+            response => {
+                // nothing was put, so lease granted for key isn't needed
+                if let Some(id) = granted_lease {
+                    let _ = client.lease_revoke(id).await;
+                }
+                match response {
+                    Ok(x) => x,
+                    Err(err) => return Err(self.explain_write_error(err).await),
+                }
+            }
+        };
+
+        let current = match response.op_responses().first() {
+            Some(TxnOpResponse::Get(x)) => x.kvs().first().map(TryInto::try_into).transpose()?,
+            _ => bail!("Unexpected transaction response"),
+        };
+        Ok(PutResult::Conflict(current))
+    }
+
+    pub fn is_staging(&self) -> bool {
+        self.staging.lock().expect("Lock not poisoned").is_enabled
+    }
+
+    pub fn set_staging(&self, value: bool) {
+        self.staging.lock().expect("Lock not poisoned").is_enabled = value;
+    }
+
+    pub fn staged_changes(&self) -> Vec<StagedChange> {
+        self.staging
+            .lock()
+            .expect("Lock not poisoned")
+            .changes
+            .clone()
+    }
+
+    /// Value of key, staged to be put, if any.
+    pub fn staged_value(&self, key: &str) -> Option<String> {
+        let staging = self.staging.lock().expect("Lock not poisoned");
+        staging.changes.iter().find_map(|x| match x {
+            StagedChange::Put { key: k, value, .. } if k == key => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Queue change to be committed later.
+    ///
+    /// Put or delete replaces earlier staged put or delete of the same key, keeping revision,
+    /// observed by the earlier one. Renames can't be combined with other changes of their keys.
+    pub fn stage(&self, change: StagedChange) -> Result<()> {
+        self.ensure_writable()?;
+        let mut staging = self.staging.lock().expect("Lock not poisoned");
+
+        let keys = change.keys();
+        let existing = staging
+            .changes
+            .iter()
+            .position(|x| x.keys().iter().any(|k| keys.contains(k)));
+        let Some(idx) = existing else {
+            staging.changes.push(change);
+            return Ok(());
+        };
+
+        let (old_value, mod_revision) = match &staging.changes[idx] {
+            StagedChange::Put {
+                old_value,
+                mod_revision,
+                ..
+            } => (old_value.clone(), *mod_revision),
+            StagedChange::Delete {
+                old_value,
+                mod_revision,
+                ..
+            } => (Some(old_value.clone()), *mod_revision),
+            StagedChange::Rename { from, .. } => {
+                bail!("Rename of '{from}' is already staged")
+            }
+        };
+        staging.changes[idx] = match change {
+            StagedChange::Put {
+                key, value, lease, ..
+            } => StagedChange::Put {
+                key,
+                value,
+                old_value,
+                mod_revision,
+                lease,
+            },
+            // deleting key, which doesn't exist yet, means not creating it
+            StagedChange::Delete { .. } if mod_revision == 0 => {
+                staging.changes.remove(idx);
+                return Ok(());
+            }
+            StagedChange::Delete { key, .. } => StagedChange::Delete {
+                key,
+                old_value: old_value.unwrap_or_default(),
+                mod_revision,
+            },
+            StagedChange::Rename { from, .. } => {
+                bail!("Key '{from}' already has staged changes")
+            }
+        };
+        Ok(())
+    }
+
+    /// Stage deletion of key, which exists now or is staged to be created.
+    pub async fn stage_delete(&self, key: &str) -> Result<()> {
+        let change = match self.find_key(key).await? {
+            Some(x) => StagedChange::Delete {
+                key: key.to_string(),
+                old_value: x.value,
+                mod_revision: x.mod_revision,
+            },
+            None if self.staged_value(key).is_some() => StagedChange::Delete {
+                key: key.to_string(),
+                old_value: String::new(),
+                mod_revision: 0,
+            },
+            None => bail!("Key not found"),
+        };
+        self.stage(change)
+    }
+
+    /// Stage renaming of key to another one, which must not exist.
+    pub async fn stage_rename(&self, from: &str, to: &str) -> Result<()> {
+        let change = self.load_rename(from, to).await?;
+        self.stage(change)
+    }
+
+    /// Rename key at once.
+    pub async fn rename_key(&self, from: &str, to: &str) -> Result<CommitResult> {
+        self.ensure_writable()?;
+        let change = self.load_rename(from, to).await?;
+        self.commit_changes(&[change]).await
+    }
+
+    async fn load_rename(&self, from: &str, to: &str) -> Result<StagedChange> {
+        if from == to {
+            bail!("Key can't be renamed to itself");
+        }
+        if self.find_key(to).await?.is_some() {
+            bail!("Key '{to}' already exists");
+        }
+        let current = self.get_key(from).await?;
+        Ok(StagedChange::Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+            value: current.value,
+            mod_revision: current.mod_revision,
+            lease: current.lease,
+        })
+    }
+
+    pub fn unstage(&self, idx: usize) {
+        let mut staging = self.staging.lock().expect("Lock not poisoned");
+        if idx < staging.changes.len() {
+            staging.changes.remove(idx);
+        }
+    }
+
+    pub fn discard_staged(&self) {
+        self.staging
+            .lock()
+            .expect("Lock not poisoned")
+            .changes
+            .clear();
+    }
+
+    /// Commit all staged changes in single transaction. They are discarded on success and
+    /// kept on conflict.
+    pub async fn commit_staged(&self) -> Result<CommitResult> {
+        let changes = self.staged_changes();
+        let result = self.commit_changes(&changes).await?;
+        if let CommitResult::Done { .. } = result {
+            self.discard_staged();
+        }
+        Ok(result)
+    }
+
+    /// Apply changes atomically, if none of their keys were modified since they were observed.
+    async fn commit_changes(&self, changes: &[StagedChange]) -> Result<CommitResult> {
+        self.ensure_writable()?;
+        let op_count = changes
+            .iter()
+            .map(|x| match x {
+                StagedChange::Rename { .. } => 2,
+                _ => 1,
+            })
+            .sum::<usize>();
+        let compare_count = changes.iter().map(|x| x.guards().len()).sum::<usize>();
+        if op_count.max(compare_count) > MAX_TXN_OPS {
+            bail!(
+                "Changes need {} operations in single transaction, but etcd allows only \
+                 {MAX_TXN_OPS} by default, commit fewer changes at once",
+                op_count.max(compare_count)
+            );
+        }
+        let mut client = self.etcd_client();
+
+        let mut compares = vec![];
+        let mut ops = vec![];
+        let mut granted_leases = vec![];
+        for change in changes {
+            compares.extend(
+                change
+                    .guards()
+                    .into_iter()
+                    .map(|(key, x)| Compare::mod_revision(key, CompareOp::Equal, x)),
+            );
+            match change {
+                StagedChange::Put {
+                    key,
+                    value,
+                    mod_revision,
+                    lease,
+                    ..
+                } => {
+                    let (options, granted) =
+                        match put_options(&mut client, *lease, *mod_revision).await {
+                            Ok(x) => x,
+                            Err(err) => {
+                                revoke_leases(&mut client, &granted_leases).await;
+                                return Err(err);
+                            }
+                        };
+                    granted_leases.extend(granted);
+                    ops.push(TxnOp::put(key.as_str(), value.as_str(), options));
+                }
+                StagedChange::Delete { key, .. } => {
+                    ops.push(TxnOp::delete(key.as_str(), None));
+                }
+                StagedChange::Rename {
+                    from,
+                    to,
+                    value,
+                    lease,
+                    ..
+                } => {
+                    let options = (*lease != 0).then(|| PutOptions::new().with_lease(*lease));
+                    ops.push(TxnOp::put(to.as_str(), value.as_str(), options));
+                    ops.push(TxnOp::delete(from.as_str(), None));
+                }
+            }
+        }
+        let guards = changes
+            .iter()
+            .flat_map(StagedChange::guards)
+            .collect::<Vec<_>>();
+        let txn = Txn::new().when(compares).and_then(ops).or_else(
+            guards
+                .iter()
+                .map(|(key, _)| TxnOp::get(key.as_str(), None))
+                .collect::<Vec<_>>(),
+        );
+
+        let response = match client.txn(txn).await {
+            Ok(x) if x.succeeded() => {
+                return Ok(CommitResult::Done {
+                    revision: x.header().map_or(0, |x| x.revision()),
+                })
+            }
+            response => {
+                // nothing was put, so leases granted for keys aren't needed
+                revoke_leases(&mut client, &granted_leases).await;
+                match response {
+                    Ok(x) => x,
+                    Err(err) => return Err(self.explain_write_error(err).await),
+                }
+            }
+        };
+
+        let mut modified = vec![];
+        for ((key, expected), response) in guards.iter().zip(response.op_responses()) {
+            let TxnOpResponse::Get(x) = response else {
+                bail!("Unexpected transaction response");
+            };
+            let mod_revision = x.kvs().first().map_or(0, |x| x.mod_revision());
+            if mod_revision != *expected {
+                modified.push(key.clone());
+            }
+        }
+        Ok(CommitResult::Conflict(modified))
+    }
+
+    /// Execute transaction atomically.
+    pub async fn execute_txn(&self, transaction: &Transaction) -> Result<TxnOutcome> {
+        // transaction of only gets is allowed in time-travel mode, reading historical revision
+        let is_writing = transaction
+            .success
+            .iter()
+            .chain(&transaction.failure)
+            .any(|x| !matches!(x, TxnOperation::Get { .. }));
+        if is_writing {
+            self.ensure_writable()?;
+        }
+        if let Some(x) = self.historical_revision() {
+            if !transaction.compares.is_empty() {
+                bail!(
+                    "Compares are evaluated at latest revision, so they can't be used at historical revision {x}"
+                );
+            }
+        }
+        let to_ops = |ops: &[TxnOperation]| {
+            ops.iter()
+                .map(|x| match x {
+                    TxnOperation::Put { key, value } => {
+                        TxnOp::put(key.as_str(), value.as_str(), None)
+                    }
+                    TxnOperation::Get { key } => TxnOp::get(
+                        key.as_str(),
+                        Some(GetOptions::new().with_revision(self.read_revision())),
+                    ),
+                    TxnOperation::Delete { key } => TxnOp::delete(key.as_str(), None),
+                })
+                .collect::<Vec<_>>()
+        };
+        let txn = Txn::new()
+            .when(
+                transaction
+                    .compares
+                    .iter()
+                    .map(to_compare)
+                    .collect::<Vec<_>>(),
+            )
+            .and_then(to_ops(&transaction.success))
+            .or_else(to_ops(&transaction.failure));
+        let response = match self.etcd_client().txn(txn).await {
+            Ok(x) => x,
+            Err(err) => return Err(self.explain_write_error(err).await),
+        };
+
+        let responses = response
+            .op_responses()
+            .into_iter()
+            .map(|x| {
+                Ok(match x {
+                    TxnOpResponse::Put(_) => TxnOpOutcome::Put,
+                    // transaction was already committed, so its results must not be lost
+                    TxnOpResponse::Get(x) => {
+                        TxnOpOutcome::Get(x.kvs().first().map(KeyValue::from_lossy))
+                    }
+                    TxnOpResponse::Delete(x) => TxnOpOutcome::Delete(x.deleted()),
+                    TxnOpResponse::Txn(_) => bail!("Unexpected nested transaction response"),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(TxnOutcome {
+            succeeded: response.succeeded(),
+            revision: response.header().map_or(0, |x| x.revision()),
+            responses,
+        })
+    }
+
+    /// Replay changes of key, including deletions, from the oldest revision, which isn't
+    /// compacted, up to read revision.
+    ///
+    /// Walking back with `mod_revision` reads can't be used, because read at revision after
+    /// deletion returns nothing, so neither deletion nor values before it could be found.
+    pub async fn load_key_history(&self, key: &str) -> Result<KeyHistory> {
+        let mut client = self.etcd_client();
+        let until = match self.read_revision() {
+            0 => client
+                .get(key, Some(GetOptions::new().with_count_only()))
+                .await?
+                .header()
+                .map_or(0, |x| x.revision()),
+            x => x,
+        };
+
+        let mut history = KeyHistory {
+            revisions: vec![],
+            is_compacted: false,
+            is_truncated: false,
+        };
+        let mut start_revision = 1;
+        // revisions are collected oldest first and reversed at the end
+        while let Some(compact_revision) = self
+            .replay_key_history(key, start_revision, until, &mut history)
+            .await?
+        {
+            if compact_revision > until {
+                bail!("Revision {until} of key was compacted");
+            }
+            history.is_compacted = true;
+            history.revisions.clear();
+            // value at compaction point is still available, unlike changes before it
+            let options = GetOptions::new().with_revision(compact_revision);
+            let response = client.get(key, Some(options)).await?;
+            if let Some(x) = response.kvs().first() {
+                history.revisions.push(x.try_into()?);
+            }
+            start_revision = compact_revision + 1;
+        }
+
+        history.revisions.reverse();
+        if history.revisions.len() > MAX_KEY_HISTORY {
+            history.revisions.truncate(MAX_KEY_HISTORY);
+            history.is_truncated = true;
+        }
+        Ok(history)
+    }
+
+    /// Append changes of key from `start_revision` up to `until` to `history`. Returns
+    /// revision of compaction, if `start_revision` was already compacted.
+    async fn replay_key_history(
+        &self,
+        key: &str,
+        start_revision: i64,
+        until: i64,
+        history: &mut KeyHistory,
+    ) -> Result<Option<i64>> {
+        if start_revision > until {
+            return Ok(None);
+        }
+        let options = WatchOptions::new().with_start_revision(start_revision);
+        let (mut watcher, mut stream) = self.etcd_client().watch(key, Some(options)).await?;
+        let mut progress_interval = interval(WATCH_PROGRESS_INTERVAL);
+        progress_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            let response = tokio::select! {
+                response = stream.message() => match response? {
+                    Some(x) => x,
+                    None => bail!("Watch stream closed"),
+                },
+                // etcd responds to progress request only after all past events were sent
+                _ = progress_interval.tick() => {
+                    watcher.request_progress().await?;
+                    continue;
+                }
+            };
+
+            if response.canceled() {
+                if response.compact_revision() > 0 {
+                    return Ok(Some(response.compact_revision()));
+                }
+                bail!("Watch cancelled: {}", response.cancel_reason());
+            }
+            // progress notification, its header revision is the one, up to which all events
+            // were already sent
+            if response.events().is_empty()
+                && !response.created()
+                && response.header().map_or(0, |x| x.revision()) >= until
+            {
+                return Ok(None);
+            }
+
+            for event in response.events() {
+                let Some(kv) = event.kv() else {
+                    continue;
+                };
+                if kv.mod_revision() > until {
+                    return Ok(None);
+                }
+                let revision = match event.event_type() {
+                    EventType::Put => kv.try_into()?,
+                    // tombstone, as etcd reports it
+                    EventType::Delete => KeyValue {
+                        value: String::new(),
+                        create_revision: 0,
+                        mod_revision: kv.mod_revision(),
+                        version: 0,
+                        lease: 0,
+                    },
+                };
+                history.revisions.push(revision);
+                // drop oldest revisions in batches, rest of them are dropped once loaded
+                if history.revisions.len() >= 2 * MAX_KEY_HISTORY {
+                    history.revisions.drain(..MAX_KEY_HISTORY);
+                    history.is_truncated = true;
+                }
+            }
+        }
+    }
+
+    /// List keys, which were added, removed or changed between `revision` and latest one.
+    pub async fn diff_keyspace(&self, revision: i64) -> Result<Vec<KeyChange>> {
+        let old = self.load_keyspace(revision).await?;
+        let mut new = self.load_keyspace(0).await?;
+
+        let mut changes = vec![];
+        for (key, old_value) in old {
+            match new.remove(&key) {
+                Some(new_value) if new_value == old_value => {}
+                new_value => changes.push(KeyChange {
+                    key,
+                    old_value: Some(old_value),
+                    new_value,
+                }),
+            }
+        }
+        changes.extend(new.into_iter().map(|(key, new_value)| KeyChange {
+            key,
+            old_value: None,
+            new_value: Some(new_value),
+        }));
+        changes.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Ok(changes)
+    }
+
+    async fn load_keyspace(&self, revision: i64) -> Result<BTreeMap<String, String>> {
+        let options = GetOptions::new().with_all_keys().with_revision(revision);
+        self.etcd_client()
+            .get(vec![], Some(options))
+            .await?
+            .kvs()
+            .iter()
+            .map(|x| {
+                Ok((
+                    x.key_str()?.to_string(),
+                    String::from_utf8_lossy(x.value()).into_owned(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Watch `key` (or all keys with prefix `key`) starting from `start_revision` (0 means
+    /// from now), sending events to `tx`.
+    ///
+    /// Watch is restarted from last seen revision on errors. Returns when `tx` is closed.
+    pub async fn watch(
+        &self,
+        key: String,
+        is_prefix: bool,
+        mut start_revision: i64,
+        tx: UnboundedSender<WatchUpdate>,
+    ) -> Result<()> {
+        loop {
+            let Err(err) = self
+                .watch_once(&key, is_prefix, &mut start_revision, &tx)
+                .await
+            else {
+                return Ok(());
+            };
+            if tx.send(WatchUpdate::Disconnected(err.to_string())).is_err() {
+                return Ok(());
+            }
+            let _ = self.tick();
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Watch until error. Returns `Ok` only when `tx` is closed.
+    async fn watch_once(
+        &self,
+        key: &str,
+        is_prefix: bool,
+        start_revision: &mut i64,
+        tx: &UnboundedSender<WatchUpdate>,
+    ) -> Result<()> {
+        let mut options = WatchOptions::new().with_prev_key();
+        if is_prefix {
+            options = options.with_prefix();
+        }
+        if *start_revision > 0 {
+            options = options.with_start_revision(*start_revision);
+        }
+        let (_watcher, mut stream) = self.etcd_client().watch(key, Some(options)).await?;
+
+        while let Some(response) = stream.message().await? {
+            if response.canceled() {
+                if response.compact_revision() > 0 {
+                    // events before compaction are lost, nothing to do about it
+                    *start_revision = response.compact_revision();
+                }
+                bail!("Watch cancelled: {}", response.cancel_reason());
+            }
+            // watch from now must be resumed from revision it started at, not from one of
+            // reconnection, even if no events were received
+            if *start_revision == 0 {
+                if let Some(header) = response.header() {
+                    *start_revision = header.revision() + 1;
+                }
+            }
+
+            for event in response.events() {
+                let Some(kv) = event.kv() else {
+                    continue;
+                };
+                let kind = match event.event_type() {
+                    EventType::Put => WatchEventKind::Put,
+                    EventType::Delete => WatchEventKind::Delete,
+                };
+                let watch_event = WatchEvent {
+                    revision: kv.mod_revision(),
+                    kind,
+                    // decoding error would be repeated on every reconnection
+                    key: String::from_utf8_lossy(kv.key()).into_owned(),
+                    value: (kind == WatchEventKind::Put)
+                        .then(|| String::from_utf8_lossy(kv.value()).into_owned()),
+                    prev_value: event
+                        .prev_kv()
+                        .map(|x| String::from_utf8_lossy(x.value()).into_owned()),
+                    time: SystemTime::now(),
+                    is_lossy: [
+                        Some(kv.key()),
+                        Some(kv.value()),
+                        event.prev_kv().map(|x| x.value()),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .any(|x| std::str::from_utf8(x).is_err()),
+                };
+                *start_revision = watch_event.revision + 1;
+                if tx.send(WatchUpdate::Event(watch_event)).is_err() {
+                    return Ok(());
+                }
+            }
+            let _ = self.tick();
+        }
+
+        bail!("Watch stream closed")
+    }
+
+    /// List all leases, skipping ones which expired while being listed.
+    pub async fn load_leases(&self) -> Result<Vec<Lease>> {
+        let mut client = self.etcd_client();
+        let response = client.leases().await?;
+
+        let mut leases = vec![];
+        for status in response.leases() {
+            let options = LeaseTimeToLiveOptions::new().with_keys();
+            let response = client
+                .lease_time_to_live(status.id(), Some(options))
+                .await?;
+            // TTL of -1 means lease has expired
+            if response.ttl() < 0 {
+                continue;
+            }
+            leases.push(Lease {
+                id: response.id(),
+                granted_ttl: response.granted_ttl(),
+                ttl: response.ttl(),
+                keys: response
+                    .keys()
+                    .iter()
+                    .map(|x| String::from_utf8_lossy(x).into_owned())
+                    .collect(),
+            });
+        }
+        leases.sort_by_key(|x| x.id);
+
+        Ok(leases)
+    }
+
+    /// Remaining TTL of lease in seconds, `None` if it has expired.
+    pub async fn lease_ttl(&self, id: i64) -> Result<Option<i64>> {
+        let response = self.etcd_client().lease_time_to_live(id, None).await?;
+        Ok((response.ttl() >= 0).then_some(response.ttl()))
+    }
+
+    /// Revoke lease, deleting all keys attached to it.
+    pub async fn revoke_lease(&self, id: i64) -> Result<()> {
+        self.ensure_writable()?;
+        let _ = self.etcd_client().lease_revoke(id).await?;
+        Ok(())
+    }
+
+    /// Renew lease once, returning its new TTL.
+    pub async fn keep_alive_lease(&self, id: i64) -> Result<i64> {
+        self.ensure_writable()?;
+        self.renew_lease(id).await
+    }
+
+    async fn renew_lease(&self, id: i64) -> Result<i64> {
+        // opening keep-alive stream already renews lease, but drops response with its TTL
+        let _ = self.etcd_client().lease_keep_alive(id).await?;
+        match self.lease_ttl(id).await? {
+            Some(x) => Ok(x),
+            None => bail!("Lease has expired"),
+        }
+    }
+
+    /// Keys, put with session lease. They are deleted, when TUI exits.
+    pub fn ephemeral_keys(&self) -> Vec<String> {
+        self.session.lock().expect("Lock not poisoned").keys.clone()
+    }
+
+    /// Put key, which must not exist, with session lease.
+    pub async fn put_ephemeral_key(&self, key: &str, value: String) -> Result<PutResult> {
+        self.ensure_writable()?;
+        let lease_id = self.session_lease().await?;
+        let result = self
+            .put_key(key, value, 0, PutLease::Existing(lease_id))
+            .await?;
+        if let PutResult::Done = result {
+            let mut session = self.session.lock().expect("Lock not poisoned");
+            if !session.keys.iter().any(|x| x == key) {
+                session.keys.push(key.to_string());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get ID of session lease, granting it and starting its keep-alive if needed.
+    async fn session_lease(&self) -> Result<i64> {
+        if let Some(x) = self.session.lock().expect("Lock not poisoned").lease_id {
+            return Ok(x);
+        }
+
+        let id = self
+            .etcd_client()
+            .lease_grant(SESSION_LEASE_TTL, None)
+            .await?
+            .id();
+        {
+            let mut session = self.session.lock().expect("Lock not poisoned");
+            session.lease_id = Some(id);
+            // keys of previous lease are gone with it
+            session.keys.clear();
+        }
+        spawn(self.clone().keep_session_lease_alive(id));
+        Ok(id)
+    }
+
+    pub fn is_session_lease(&self, id: i64) -> bool {
+        self.session.lock().expect("Lock not poisoned").lease_id == Some(id)
+    }
+
+    /// Renew session lease, until it is revoked or expires.
+    async fn keep_session_lease_alive(self, id: i64) {
+        let interval = Duration::from_secs(SESSION_LEASE_TTL as u64 / 3);
+        // keep-alive stream is reused for all renewals, until it fails
+        let mut keep_alive = None;
+        while self.is_session_lease(id) {
+            // session lease is renewed in time-travel mode too
+            let result = match keep_alive {
+                Some((ref mut keeper, ref mut stream)) => {
+                    renew_lease_with(keeper, stream).await.map(|_| ())
+                }
+                // opening keep-alive stream already renews lease once
+                None => match self.etcd_client().lease_keep_alive(id).await {
+                    Ok(x) => {
+                        keep_alive = Some(x);
+                        Ok(())
+                    }
+                    Err(err) => Err(err.into()),
+                },
+            };
+            match result {
+                Ok(()) => sleep(interval).await,
+                Err(_) if self.lease_ttl(id).await.is_ok_and(|x| x.is_none()) => {
+                    let mut session = self.session.lock().expect("Lock not poisoned");
+                    if session.lease_id == Some(id) {
+                        *session = Session::default();
+                    }
+                    drop(session);
+                    let _ = self.tick();
+                }
+                // connection problem, lease may still be renewed in time
+                Err(_) => {
+                    keep_alive = None;
+                    sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    /// Revoke session lease, deleting all ephemeral keys.
+    pub async fn revoke_session_lease(&self) -> Result<()> {
+        let session = std::mem::take(&mut *self.session.lock().expect("Lock not poisoned"));
+        let Some(id) = session.lease_id else {
+            return Ok(());
+        };
+        let mut client = self.etcd_client();
+        // runs on exit, which must not hang on unreachable cluster
+        let _ = timeout(MEMBER_TIMEOUT, client.lease_revoke(id)).await??;
+        Ok(())
+    }
+
+    /// Waiters for lock `name` in order, in which they acquire it, holder first.
+    pub async fn load_lock_waiters(&self, name: &str) -> Result<Vec<LockWaiter>> {
+        // keys are created by etcd as `<name>/<lease ID>`
+        let options = GetOptions::new()
+            .with_prefix()
+            .with_sort(SortTarget::Create, SortOrder::Ascend);
+        let response = self
+            .etcd_client()
+            .get(format!("{name}/"), Some(options))
+            .await?;
+        let waiters = join_all(response.kvs().iter().map(|kv| async move {
+            let ttl = match kv.lease() {
+                0 => None,
+                x => self.lease_ttl(x).await?,
+            };
+            anyhow::Ok(LockWaiter {
+                key: kv.key_str()?.to_string(),
+                create_revision: kv.create_revision(),
+                lease: kv.lease(),
+                ttl,
+            })
+        }))
+        .await;
+        waiters.into_iter().collect()
+    }
+
+    /// Wait until lock `name` is acquired with session lease, so that it is held until
+    /// released or app exits. Return key of lock.
+    pub async fn acquire_lock(&self, name: &str) -> Result<String> {
+        self.ensure_writable()?;
+        let lease_id = self.session_lease().await?;
+        let options = LockOptions::new().with_lease(lease_id);
+        let response = self.etcd_client().lock(name, Some(options)).await?;
+        Ok(String::from_utf8_lossy(response.key()).into_owned())
+    }
+
+    /// Release lock, acquired by waiter with `key`, or stop waiting for it.
+    pub async fn release_lock(&self, key: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let _ = self.etcd_client().unlock(key).await?;
         Ok(())
     }
 
     pub async fn delete_key(&self, key: &str) -> Result<()> {
+        self.ensure_writable()?;
         let _ = self.etcd_client().delete(key, None).await?;
         Ok(())
     }
@@ -70,3 +1966,151 @@ impl SharedState {
         self.send_event(Event::Tick)
     }
 }
+
+fn connect_options(cli: &Cli, credentials: Option<(String, String)>) -> ConnectOptions {
+    let mut options = ConnectOptions::new();
+    // connection is lazy unless authentication is required, so skip it to allow replay
+    // without etcd
+    if let (Some((user, password)), None) = (credentials, &cli.replay) {
+        options = options.with_user(user, password);
+    }
+    options
+}
+
+/// Options to put key, which was at `mod_revision`, with `lease`. Returns ID of lease, granted
+/// for key, if any.
+async fn put_options(
+    client: &mut Client,
+    lease: PutLease,
+    mod_revision: i64,
+) -> Result<(Option<PutOptions>, Option<i64>)> {
+    let options = match lease {
+        PutLease::None => None,
+        // there is no lease to keep for key, which doesn't exist
+        PutLease::Keep if mod_revision == 0 => None,
+        PutLease::Keep => Some(PutOptions::new().with_ignore_lease()),
+        PutLease::Existing(id) => Some(PutOptions::new().with_lease(id)),
+        PutLease::Grant(ttl) => {
+            let id = client.lease_grant(ttl, None).await?.id();
+            return Ok((Some(PutOptions::new().with_lease(id)), Some(id)));
+        }
+    };
+    Ok((options, None))
+}
+
+/// Revoke leases, which were granted for keys, but aren't needed anymore.
+async fn revoke_leases(client: &mut Client, ids: &[i64]) {
+    for id in ids {
+        let _ = client.lease_revoke(*id).await;
+    }
+}
+
+/// Renew lease once over existing keep-alive stream, returning its new TTL.
+async fn renew_lease_with(
+    keeper: &mut LeaseKeeper,
+    stream: &mut LeaseKeepAliveStream,
+) -> Result<i64> {
+    keeper.keep_alive().await?;
+    match stream.message().await? {
+        Some(x) if x.ttl() > 0 => Ok(x.ttl()),
+        Some(_) => bail!("Lease has expired"),
+        None => bail!("Keep alive stream closed"),
+    }
+}
+
+fn to_compare(compare: &TxnCompare) -> Compare {
+    let op = match compare.operator {
+        CompareOperator::Equal => CompareOp::Equal,
+        CompareOperator::NotEqual => CompareOp::NotEqual,
+        CompareOperator::Greater => CompareOp::Greater,
+        CompareOperator::Less => CompareOp::Less,
+    };
+    let key = compare.key.as_str();
+    match compare.target {
+        CompareTarget::Value(ref x) => Compare::value(key, op, x.as_str()),
+        CompareTarget::Version(x) => Compare::version(key, op, x),
+        CompareTarget::CreateRevision(x) => Compare::create_revision(key, op, x),
+        CompareTarget::ModRevision(x) => Compare::mod_revision(key, op, x),
+        CompareTarget::Lease(x) => Compare::lease(key, op, x),
+    }
+}
+
+/// Error of auth request, reduced to message of etcd, e.g. `etcdserver: user name already
+/// exists`.
+fn auth_error(err: etcd_client::Error) -> anyhow::Error {
+    match err {
+        etcd_client::Error::GRpcStatus(x) => anyhow!("{}", x.message()),
+        err => err.into(),
+    }
+}
+
+fn to_permission(permission: &Permission) -> etcd_client::Permission {
+    let kind = match permission.kind {
+        PermissionKind::Read => PermissionType::Read,
+        PermissionKind::Write => PermissionType::Write,
+        PermissionKind::ReadWrite => PermissionType::Readwrite,
+    };
+    let (key, range_end) = permission.range.to_bytes();
+    etcd_client::Permission::new(kind, key).with_range_end(range_end)
+}
+
+fn from_permission(permission: &etcd_client::Permission) -> Permission {
+    let kind = match permission.get_type() {
+        x if x == PermissionType::Read as i32 => PermissionKind::Read,
+        x if x == PermissionType::Write as i32 => PermissionKind::Write,
+        _ => PermissionKind::ReadWrite,
+    };
+    Permission {
+        kind,
+        range: KeyRange::from_bytes(permission.key(), permission.range_end()),
+    }
+}
+
+fn is_permission_denied_error(err: &etcd_client::Error) -> bool {
+    matches!(err, etcd_client::Error::GRpcStatus(x) if x.message().contains("permission denied"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_put_lease() {
+        assert_eq!("none".parse::<PutLease>().unwrap(), PutLease::None);
+        assert_eq!(" keep ".parse::<PutLease>().unwrap(), PutLease::Keep);
+        assert_eq!(
+            "0x694d77aa9e38260f".parse::<PutLease>().unwrap(),
+            PutLease::Existing(0x694d77aa9e38260f)
+        );
+        assert_eq!("30s".parse::<PutLease>().unwrap(), PutLease::Grant(30));
+        assert_eq!("5m".parse::<PutLease>().unwrap(), PutLease::Grant(300));
+    }
+
+    #[test]
+    fn rejects_invalid_put_lease() {
+        for x in ["", "12", "0x", "0x0", "0xzz", "0s", "500ms", "forever"] {
+            assert!(x.parse::<PutLease>().is_err(), "'{x}' was accepted");
+        }
+    }
+
+    #[test]
+    fn displayed_put_lease_can_be_typed_back() {
+        let existing = PutLease::Existing(0x694d77aa9e38260f);
+        let shown = existing.to_string();
+        assert_eq!(shown, "lease 0x694d77aa9e38260f");
+        assert_eq!(
+            shown["lease ".len()..].parse::<PutLease>().unwrap(),
+            existing
+        );
+
+        let grant = PutLease::Grant(30);
+        let shown = grant.to_string();
+        assert_eq!(shown, "new lease with TTL 30s");
+        assert_eq!(
+            shown["new lease with TTL ".len()..]
+                .parse::<PutLease>()
+                .unwrap(),
+            grant
+        );
+    }
+}
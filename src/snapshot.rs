@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use ring::digest::{Context as DigestContext, SHA256, SHA256_OUTPUT_LEN};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+};
+
+/// Size of SHA-256 checksum, which etcd appends to snapshot.
+pub const CHECKSUM_LEN: u64 = SHA256_OUTPUT_LEN as u64;
+
+/// Snapshot file being written, which is removed unless it was completed and verified,
+/// e.g. when download was cancelled.
+///
+/// Data is hashed while being written, so file doesn't have to be read again to verify it.
+pub struct SnapshotWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    digest: DigestContext,
+    /// Last bytes written, which are hashed only once it's known they aren't checksum.
+    tail: Vec<u8>,
+    is_verified: bool,
+}
+
+impl SnapshotWriter {
+    /// Create new file at `path`, existing one is never overwritten.
+    pub async fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to create '{}'", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            writer: BufWriter::new(file),
+            size: 0,
+            digest: DigestContext::new(&SHA256),
+            tail: vec![],
+            is_verified: false,
+        })
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        self.writer.write_all(chunk).await?;
+        self.size += chunk.len() as u64;
+
+        self.tail.extend_from_slice(chunk);
+        let hashed_len = self.tail.len().saturating_sub(CHECKSUM_LEN as usize);
+        self.digest.update(&self.tail[..hashed_len]);
+        self.tail.drain(..hashed_len);
+        Ok(())
+    }
+
+    /// Flush file and verify, that it has `expected_size` bytes and its content matches
+    /// appended checksum. Return size of file.
+    pub async fn finish(mut self, expected_size: u64) -> Result<u64> {
+        self.writer.flush().await?;
+        self.writer.get_ref().sync_all().await?;
+
+        let size = tokio::fs::metadata(&self.path).await?.len();
+        if size != self.size || size != expected_size {
+            bail!(
+                "Snapshot file has {size} bytes, but {expected_size} bytes were expected and {} received",
+                self.size
+            );
+        }
+        if size < CHECKSUM_LEN {
+            bail!("Snapshot is too short to contain checksum");
+        }
+        if self.digest.clone().finish().as_ref() != self.tail {
+            bail!("Snapshot checksum mismatch, file is corrupted");
+        }
+
+        self.is_verified = true;
+        Ok(size)
+    }
+}
+
+impl Drop for SnapshotWriter {
+    fn drop(&mut self) {
+        if !self.is_verified {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::shared_state::KeyValue;
+
+/// Field of key, compared in transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompareTarget {
+    Value(String),
+    Version(i64),
+    CreateRevision(i64),
+    ModRevision(i64),
+    /// Lease ID, 0 means no lease.
+    Lease(i64),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompareOperator {
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+}
+
+/// Compare clause in `etcdctl txn` syntax, e.g. `mod("key") > "5"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxnCompare {
+    pub key: String,
+    pub operator: CompareOperator,
+    pub target: CompareTarget,
+}
+
+/// Operation in `etcdctl txn` syntax, e.g. `put key value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TxnOperation {
+    Put { key: String, value: String },
+    Get { key: String },
+    Delete { key: String },
+}
+
+/// Transaction, consisting of compares and operations for both its branches.
+#[derive(Clone, Debug, Default)]
+pub struct Transaction {
+    pub compares: Vec<TxnCompare>,
+    pub success: Vec<TxnOperation>,
+    pub failure: Vec<TxnOperation>,
+}
+
+/// Response to single operation of executed transaction.
+#[derive(Clone, Debug)]
+pub enum TxnOpOutcome {
+    Put,
+    /// `None` if key doesn't exist.
+    Get(Option<KeyValue>),
+    /// Number of deleted keys.
+    Delete(i64),
+}
+
+#[derive(Clone, Debug)]
+pub struct TxnOutcome {
+    /// Whether compares succeeded and success branch ran.
+    pub succeeded: bool,
+    pub revision: i64,
+    /// Responses to operations of branch, which ran.
+    pub responses: Vec<TxnOpOutcome>,
+}
+
+impl fmt::Display for CompareOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let x = match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Greater => ">",
+            Self::Less => "<",
+        };
+        write!(f, "{x}")
+    }
+}
+
+impl fmt::Display for TxnCompare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, operand) = match &self.target {
+            CompareTarget::Value(x) => ("value", x.clone()),
+            CompareTarget::Version(x) => ("version", x.to_string()),
+            CompareTarget::CreateRevision(x) => ("create", x.to_string()),
+            CompareTarget::ModRevision(x) => ("mod", x.to_string()),
+            CompareTarget::Lease(x) => ("lease", format!("{x:x}")),
+        };
+        write!(
+            f,
+            "{name}({}) {} {}",
+            quote(&self.key),
+            self.operator,
+            quote(&operand)
+        )
+    }
+}
+
+impl FromStr for TxnCompare {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, rest) = s
+            .split_once('(')
+            .ok_or_else(|| anyhow!("Expected compare like mod(\"key\") > \"5\""))?;
+        let (key, rest) = split_token(rest, ')')?;
+        let rest = rest
+            .trim_start()
+            .strip_prefix(')')
+            .ok_or_else(|| anyhow!("Missing ')' after key"))?
+            .trim_start();
+
+        let (operator, operand) = [
+            ("!=", CompareOperator::NotEqual),
+            ("=", CompareOperator::Equal),
+            (">", CompareOperator::Greater),
+            ("<", CompareOperator::Less),
+        ]
+        .into_iter()
+        .find_map(|(prefix, op)| rest.strip_prefix(prefix).map(|x| (op, x)))
+        .ok_or_else(|| anyhow!("Expected one of '=', '!=', '>', '<'"))?;
+        let operand = unquote(operand.trim())?;
+        let operand = operand.as_str();
+
+        let parse_number = || {
+            operand
+                .parse::<i64>()
+                .map_err(|_| anyhow!("Invalid number '{operand}'"))
+        };
+        let target = match name.trim() {
+            "value" | "val" => CompareTarget::Value(operand.to_string()),
+            "version" | "ver" => CompareTarget::Version(parse_number()?),
+            "create" | "c" => CompareTarget::CreateRevision(parse_number()?),
+            "mod" | "m" => CompareTarget::ModRevision(parse_number()?),
+            "lease" => CompareTarget::Lease(
+                i64::from_str_radix(operand, 16)
+                    .map_err(|_| anyhow!("Invalid lease ID '{operand}'"))?,
+            ),
+            x => {
+                bail!("Unknown compare target '{x}', expected value, version, create, mod or lease")
+            }
+        };
+
+        Ok(Self {
+            key,
+            operator,
+            target,
+        })
+    }
+}
+
+impl fmt::Display for TxnOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Put { key, value } => write!(f, "put {} {}", quote(key), quote(value)),
+            Self::Get { key } => write!(f, "get {}", quote(key)),
+            Self::Delete { key } => write!(f, "del {}", quote(key)),
+        }
+    }
+}
+
+impl FromStr for TxnOperation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim_start();
+        let (name, rest) = s.split_once(' ').unwrap_or((s, ""));
+        let (key, rest) = split_token(rest, ' ')?;
+        let rest = rest.trim();
+
+        let operation = match name {
+            "put" => Self::Put {
+                key,
+                value: unquote(rest)?,
+            },
+            "get" => Self::Get { key },
+            "del" | "delete" => Self::Delete { key },
+            x => bail!("Unknown operation '{x}', expected put, get or del"),
+        };
+        if !matches!(operation, Self::Put { .. }) && !rest.is_empty() {
+            bail!("Unexpected '{rest}' after key");
+        }
+        Ok(operation)
+    }
+}
+
+/// Quote `s`, escaping characters, which would end it or break it into lines.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for x in s.chars() {
+        match x {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            x => quoted.push(x),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Split quoted string from rest of `s`, which must start with opening quote.
+fn split_quoted(s: &str) -> Result<(String, &str)> {
+    let mut unquoted = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((idx, x)) = chars.next() {
+        match x {
+            '"' => return Ok((unquoted, &s[idx + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => unquoted.push('\n'),
+                Some((_, 'r')) => unquoted.push('\r'),
+                Some((_, 't')) => unquoted.push('\t'),
+                Some((_, x)) => unquoted.push(x),
+                None => break,
+            },
+            x => unquoted.push(x),
+        }
+    }
+    bail!("Missing closing quote")
+}
+
+/// Split leading token, either quoted or ending before `end`, from rest of `s`.
+fn split_token(s: &str, end: char) -> Result<(String, &str)> {
+    let s = s.trim_start();
+    let (token, rest) = if s.starts_with('"') {
+        split_quoted(s)?
+    } else {
+        let (token, rest) = s.split_at(s.find(end).unwrap_or(s.len()));
+        (token.to_string(), rest)
+    };
+    if token.is_empty() {
+        bail!("Missing key");
+    }
+    Ok((token, rest))
+}
+
+/// Unquote `s`, if it is quoted, or take it as it is.
+fn unquote(s: &str) -> Result<String> {
+    if !s.starts_with('"') {
+        return Ok(s.to_string());
+    }
+    match split_quoted(s)? {
+        (x, "") => Ok(x),
+        (_, rest) => bail!("Unexpected '{rest}' after closing quote"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compares() {
+        let compare: TxnCompare = r#"mod("/a") > "5""#.parse().unwrap();
+        assert_eq!(
+            compare,
+            TxnCompare {
+                key: "/a".into(),
+                operator: CompareOperator::Greater,
+                target: CompareTarget::ModRevision(5),
+            }
+        );
+
+        let compare: TxnCompare = "value(/a) != x y".parse().unwrap();
+        assert_eq!(compare.operator, CompareOperator::NotEqual);
+        assert_eq!(compare.target, CompareTarget::Value("x y".into()));
+
+        let compare: TxnCompare = r#"lease("/a") = "694d""#.parse().unwrap();
+        assert_eq!(compare.target, CompareTarget::Lease(0x694d));
+    }
+
+    #[test]
+    fn rejects_invalid_compares() {
+        assert!("mod /a > 5".parse::<TxnCompare>().is_err());
+        assert!(r#"mod("/a") ~ "5""#.parse::<TxnCompare>().is_err());
+        assert!(r#"mod("/a") > "x""#.parse::<TxnCompare>().is_err());
+        assert!(r#"size("/a") > "5""#.parse::<TxnCompare>().is_err());
+        assert!(r#"mod("") > "5""#.parse::<TxnCompare>().is_err());
+        assert!(r#"value("/a) = "5""#.parse::<TxnCompare>().is_err());
+    }
+
+    #[test]
+    fn parses_operations() {
+        assert_eq!(
+            "put /a some value".parse::<TxnOperation>().unwrap(),
+            TxnOperation::Put {
+                key: "/a".into(),
+                value: "some value".into(),
+            }
+        );
+        assert_eq!(
+            r#"get "/a b""#.parse::<TxnOperation>().unwrap(),
+            TxnOperation::Get { key: "/a b".into() }
+        );
+        assert_eq!(
+            "delete /a".parse::<TxnOperation>().unwrap(),
+            TxnOperation::Delete { key: "/a".into() }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        assert!("put".parse::<TxnOperation>().is_err());
+        assert!("get /a /b".parse::<TxnOperation>().is_err());
+        assert!("watch /a".parse::<TxnOperation>().is_err());
+        assert!(r#"put /a "b" c"#.parse::<TxnOperation>().is_err());
+    }
+
+    #[test]
+    fn printed_compares_parse_back() {
+        let targets = [
+            CompareTarget::Value("with \"quotes\", \\ and\nnewline".into()),
+            CompareTarget::Value(String::new()),
+            CompareTarget::Version(0),
+            CompareTarget::CreateRevision(12),
+            CompareTarget::ModRevision(34),
+            CompareTarget::Lease(0x694d77aa9e38260f),
+        ];
+        for target in targets {
+            let compare = TxnCompare {
+                key: "key) with \"special\" chars".into(),
+                operator: CompareOperator::Less,
+                target,
+            };
+            assert_eq!(compare.to_string().parse::<TxnCompare>().unwrap(), compare);
+        }
+    }
+
+    #[test]
+    fn printed_operations_parse_back() {
+        let key = "/a b \"c\" \\d".to_string();
+        let operations = [
+            TxnOperation::Put {
+                key: key.clone(),
+                value: "line 1\r\nline 2\t\"quoted\"".into(),
+            },
+            TxnOperation::Put {
+                key: key.clone(),
+                value: String::new(),
+            },
+            TxnOperation::Get { key: key.clone() },
+            TxnOperation::Delete { key },
+        ];
+        for operation in operations {
+            assert_eq!(
+                operation.to_string().parse::<TxnOperation>().unwrap(),
+                operation
+            );
+        }
+    }
+}
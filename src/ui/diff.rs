@@ -0,0 +1,33 @@
+use ratatui::{
+    style::{Style, Stylize},
+    text::Line,
+};
+use similar::{ChangeTag, TextDiff};
+
+/// Build colored unified diff of two values, one `Line` per diff line.
+pub fn unified_diff_lines(old: &str, new: &str) -> Vec<Line<'static>> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut lines = vec![];
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        lines.push(Line::styled(
+            hunk.header().to_string(),
+            Style::default().cyan(),
+        ));
+        for change in hunk.iter_changes() {
+            let (sign, style) = match change.tag() {
+                ChangeTag::Delete => ("-", Style::default().red()),
+                ChangeTag::Insert => ("+", Style::default().green()),
+                ChangeTag::Equal => (" ", Style::default()),
+            };
+            let value = change.value().trim_end_matches(['\r', '\n']);
+            lines.push(Line::styled(format!("{sign}{value}"), style));
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push(Line::styled("No changes", Style::default().italic()));
+    }
+
+    lines
+}
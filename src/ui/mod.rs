@@ -1,3 +1,5 @@
+pub use self::diff::unified_diff_lines;
+
 use std::cmp::min;
 
 use ratatui::{
@@ -6,6 +8,8 @@ use ratatui::{
     widgets::{block::Title, Block, Borders},
 };
 
+mod diff;
+
 pub type Frame<'a> = ratatui::Frame<'a, CrosstermBackend<std::io::Stderr>>;
 
 pub fn titled_block<'a, T>(title: T) -> Block<'a>
@@ -0,0 +1,184 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::shared_state::{WatchEvent, WatchEventKind};
+
+/// Watch event as stored in JSON Lines file.
+#[derive(Serialize, Deserialize)]
+struct WatchRecord {
+    revision: i64,
+    #[serde(rename = "type")]
+    kind: WatchEventKind,
+    key: String,
+    value: Option<String>,
+    prev_value: Option<String>,
+    /// RFC 3339 timestamp.
+    time: String,
+}
+
+impl From<&WatchEvent> for WatchRecord {
+    fn from(event: &WatchEvent) -> Self {
+        Self {
+            revision: event.revision,
+            kind: event.kind,
+            key: event.key.clone(),
+            value: event.value.clone(),
+            prev_value: event.prev_value.clone(),
+            time: humantime::format_rfc3339_millis(event.time).to_string(),
+        }
+    }
+}
+
+impl TryFrom<WatchRecord> for WatchEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(record: WatchRecord) -> Result<Self> {
+        Ok(Self {
+            revision: record.revision,
+            kind: record.kind,
+            key: record.key,
+            value: record.value,
+            prev_value: record.prev_value,
+            time: humantime::parse_rfc3339_weak(&record.time)?,
+            is_lossy: false,
+        })
+    }
+}
+
+/// Appends watch events to file.
+pub struct WatchRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl WatchRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open '{}'", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `events` to file. Fails on event with binary key or value, which can't be
+    /// stored as text without losing data.
+    pub fn record<'a>(&mut self, events: impl IntoIterator<Item = &'a WatchEvent>) -> Result<()> {
+        for event in events {
+            if event.is_lossy {
+                self.writer.flush()?;
+                bail!(
+                    "Change of '{}' at revision {} isn't valid UTF-8 and can't be recorded",
+                    event.key,
+                    event.revision
+                );
+            }
+            serde_json::to_writer(&mut self.writer, &WatchRecord::from(event))?;
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Load events, recorded by `WatchRecorder`.
+pub fn load_recording(path: &Path) -> Result<Vec<WatchEvent>> {
+    let file = File::open(path).with_context(|| format!("Failed to open '{}'", path.display()))?;
+
+    let mut events = vec![];
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: WatchRecord = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid record on line {}", idx + 1))?;
+        events.push(record.try_into()?);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    fn event(revision: i64, kind: WatchEventKind, value: Option<&str>) -> WatchEvent {
+        WatchEvent {
+            revision,
+            kind,
+            key: "foo".into(),
+            value: value.map(Into::into),
+            prev_value: Some("old\nvalue".into()),
+            // records keep millisecond precision
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+            is_lossy: false,
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("etcd-tui-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn recording_round_trip() {
+        let path = temp_path("round-trip.jsonl");
+        let events = [
+            event(5, WatchEventKind::Put, Some("new \"value\" \u{1f600}")),
+            event(6, WatchEventKind::Delete, None),
+        ];
+
+        let mut recorder = WatchRecorder::create(&path).unwrap();
+        recorder.record(&events[..1]).unwrap();
+        recorder.record(&events[1..]).unwrap();
+        drop(recorder);
+        let loaded = load_recording(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), events.len());
+        for (loaded, event) in loaded.iter().zip(&events) {
+            assert_eq!(loaded.revision, event.revision);
+            assert_eq!(loaded.kind, event.kind);
+            assert_eq!(loaded.key, event.key);
+            assert_eq!(loaded.value, event.value);
+            assert_eq!(loaded.prev_value, event.prev_value);
+            assert_eq!(loaded.time, event.time);
+        }
+    }
+
+    #[test]
+    fn binary_value_is_rejected() {
+        let path = temp_path("binary.jsonl");
+        let mut lossy = event(7, WatchEventKind::Put, Some("\u{fffd}"));
+        lossy.is_lossy = true;
+
+        let mut recorder = WatchRecorder::create(&path).unwrap();
+        let result = recorder.record([&event(6, WatchEventKind::Put, Some("x")), &lossy]);
+        drop(recorder);
+        let loaded = load_recording(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].revision, 6);
+    }
+}